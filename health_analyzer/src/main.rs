@@ -6,22 +6,27 @@
 extern crate alloc;
 
 use alloc::format;
+use core::{
+    ptr::addr_of,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use gpu::{
+    Boundable, BoundableExt, Color, MutBoundable, MutPositionable, Point, Positionable, Rect,
+    TextAlign,
+};
 use health_analyzer::{DamageType, DamageTypeIterator, HealthAnalyzer};
 use heap::Heap;
 use pci::PciBus;
 use plic::Plic;
+use render::{palette::medical, TextBox, Transition, TransitionKind};
 use riscv::register::{
     mcause::{Exception, Trap},
     sstatus::FS,
 };
 use riscv_rt::entry;
-use sgl::{
-    gpu::{
-        Boundable, BoundableExt, Color, MutBoundable, MutPositionable, Point, Positionable, Rect,
-        TextAlign,
-    },
-    Image, Sgl, Text,
-};
+use rtc::Rtc;
+use sgl::{Image, Sgl, Text};
 
 macro_rules! include_asset {
     ($path:literal) => {
@@ -38,8 +43,15 @@ extern "C" {
 static mut HEAP: Heap = Heap::empty();
 static mut PLUS_IMAGE: Option<Image> = None;
 static mut BIOHAZARD_IMAGE: Option<Image> = None;
+/// Set by the interrupt handler, the scan is shown by the main loop.
+static SCAN_FINISHED: AtomicBool = AtomicBool::new(false);
 
 const FONT_SIZE: f64 = 14.0;
+const REPORT_MARGIN: f64 = 4.0;
+const REPORT_COLUMNS: f64 = 2.0;
+/// Damage shown with the most severe color.
+const CRITICAL_DAMAGE: f64 = 100.0;
+const TRANSITION: Transition = Transition::new(TransitionKind::Fade, Duration::from_millis(300));
 
 fn draw_title_screen(label: &str) {
     let sgl = Sgl::mut_get().as_mut().unwrap();
@@ -50,7 +62,7 @@ fn draw_title_screen(label: &str) {
         .with_position(Point::new(sgl.bounds().hcenter(), 0.0))
         .with_color(Some(Color::green()));
 
-    let mut plus_image = unsafe { PLUS_IMAGE.clone().unwrap() };
+    let mut plus_image = unsafe { (*addr_of!(PLUS_IMAGE)).clone().unwrap() };
 
     plus_image.translate_y(8.0);
     plus_image.translate_x(sgl.bounds().hcenter() - 120.0 / 2.0);
@@ -64,14 +76,14 @@ fn draw_title_screen(label: &str) {
 
     label.translate_y(120.0 + FONT_SIZE * 2.0);
     sgl.draw_text(&label);
-
-    sgl.flush();
 }
 
 fn draw_scan_report(analyzer: &HealthAnalyzer) {
     let sgl = Sgl::mut_get().as_mut().unwrap();
-    let mut pos = Point::new(4.0, 4.0);
-    let mut max_string_width = 0.0;
+    let column_width = (sgl.bounds().width() - REPORT_MARGIN * 2.0) / REPORT_COLUMNS;
+    let bottom = sgl.bounds().height() - REPORT_MARGIN;
+    let mut column_left = REPORT_MARGIN;
+    let mut top = REPORT_MARGIN;
 
     sgl.fill_screen(Some(Color::black()));
 
@@ -92,47 +104,62 @@ fn draw_scan_report(analyzer: &HealthAnalyzer) {
             DamageType::Slash => "Порезы",
         };
 
-        let color;
+        let color = medical::SEVERITY.at(damage / CRITICAL_DAMAGE);
         let text = if damage == 0.0 {
-            color = Color::green();
             format!("{damage_name}...НЕТ")
         } else {
-            if damage > 0.0 {
-                color = Color::red();
-            } else {
-                color = Color::green();
-            }
-
             format!("{damage_name}: {damage:.1}")
         };
 
-        let text = Text::new_dynamic(&text)
-            .with_color(Some(color))
-            .with_position(pos)
-            .with_size(Some(FONT_SIZE));
-        let text_size = sgl.measure_text_bounds(&text);
-
-        max_string_width = f64::max(max_string_width, text_size.width());
-        sgl.draw_text(&text);
+        let mut text_box = TextBox::new(
+            Rect::new(column_left, top, column_left + column_width, bottom),
+            FONT_SIZE,
+        );
+        let mut measure = |line: &str| {
+            sgl.measure_text_bounds(&Text::new_dynamic(line).with_size(Some(FONT_SIZE)))
+                .width()
+        };
+        let mut layout = text_box.layout(&text, &mut measure);
+
+        // Entries aren't split between columns.
+        if layout.overflowed() && top != REPORT_MARGIN {
+            column_left += column_width;
+            top = REPORT_MARGIN;
+            text_box.set_rect(Rect::new(
+                column_left,
+                top,
+                column_left + column_width,
+                bottom,
+            ));
+            layout = text_box.layout(&text, &mut measure);
+        }
 
-        pos.y += FONT_SIZE;
+        for (index, line) in layout.lines().iter().enumerate() {
+            let text = Text::new_dynamic(line.text)
+                .with_color(Some(color))
+                .with_position(Point::new(
+                    column_left,
+                    top + index as f64 * text_box.line_height(),
+                ))
+                .with_size(Some(FONT_SIZE));
 
-        if pos.y > sgl.bounds().height() {
-            pos.y = 4.0;
-            pos.x += max_string_width;
+            sgl.draw_text(&text);
         }
+
+        top += layout.height();
     }
 
     if analyzer.has_disease() {
-        let biohazard =
-            unsafe { BIOHAZARD_IMAGE.clone().unwrap() }.with_bounds(Rect::new_from_position(
+        let biohazard = unsafe { (*addr_of!(BIOHAZARD_IMAGE)).clone().unwrap() }.with_bounds(
+            Rect::new_from_position(
                 Point::new(
                     sgl.bounds().width() - 160.0 - 25.0,
                     sgl.bounds().vcenter() - 130.0 / 2.0 - 15.0,
                 ),
                 160.0,
                 130.0,
-            ));
+            ),
+        );
 
         let mut text_position = Point::zero();
         text_position.x = biohazard.bounds().hcenter();
@@ -144,11 +171,31 @@ fn draw_scan_report(analyzer: &HealthAnalyzer) {
             .with_position(text_position)
             .with_size(Some(FONT_SIZE));
 
-        sgl.draw_image_rect(&biohazard, biohazard.bounds().clone());
+        sgl.draw_image_rect(&biohazard, *biohazard.bounds());
         sgl.draw_text(&disease_text);
     }
+}
 
-    sgl.flush();
+/// Redraws the screen with `draw` under `transition` until it ends.
+fn play_transition(transition: Transition, mut draw: impl FnMut()) {
+    let transition = transition.started(unsafe { Rtc::default().now() });
+
+    loop {
+        let now = unsafe { Rtc::default().now() };
+
+        draw();
+
+        let sgl = Sgl::mut_get().as_mut().unwrap();
+        let bounds = *sgl.bounds();
+
+        // A fade is a single rect over the whole screen.
+        transition.for_each_rect(now, bounds, |_, color| sgl.fill_screen(Some(color)));
+        sgl.flush();
+
+        if transition.is_finished(now) {
+            break;
+        }
+    }
 }
 
 #[panic_handler]
@@ -172,6 +219,7 @@ unsafe fn main() -> ! {
     BIOHAZARD_IMAGE = Some(sgl.create_image(include_asset!("biohazard.bitmap"), 431, 349));
 
     draw_title_screen("Ожидание сканирования");
+    sgl.flush();
 
     let pci = PciBus::default();
     let mut plic = Plic::default();
@@ -187,7 +235,20 @@ unsafe fn main() -> ! {
     riscv::register::mie::set_mext();
 
     loop {
-        riscv::asm::wfi();
+        if SCAN_FINISHED.swap(false, Ordering::Acquire) {
+            play_transition(TRANSITION, || draw_title_screen("Анализ..."));
+            play_transition(TRANSITION.reversed(), || draw_scan_report(&analyzer));
+        }
+
+        // Interrupts are off around the check so a scan finishing in between isn't missed, `wfi`
+        // still wakes up on a pending interrupt.
+        riscv::interrupt::disable();
+
+        if !SCAN_FINISHED.load(Ordering::Acquire) {
+            riscv::asm::wfi();
+        }
+
+        riscv::interrupt::enable();
     }
 }
 
@@ -204,8 +265,7 @@ unsafe fn external_interrupt() {
         plic.claim(irq);
 
         if irq == analyzer.device.irq_pin {
-            draw_title_screen("Анализ...");
-            draw_scan_report(&analyzer);
+            SCAN_FINISHED.store(true, Ordering::Release);
         }
     }
 }
@@ -0,0 +1,83 @@
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const FONT_PATH: &str = "assets/Greybeard-11px.ttf";
+const FONT_SIZE: f32 = 11.0;
+/// Printable ASCII and the Russian alphabet.
+const CHAR_RANGES: &[(char, char)] = &[(' ', '~'), ('Ё', 'Ё'), ('А', 'я'), ('ё', 'ё')];
+
+/// Rasterizes the font at its native size into fixed size cells, one byte per pixel with every
+/// cell stacked vertically, and generates the glyph table for `BitmapFont`. Pixels are either
+/// fully set or empty, the font is drawn pixel-exact.
+fn build_font(out_dir: &Path) {
+    println!("cargo:rerun-if-changed={}", FONT_PATH);
+
+    let data = fs::read(FONT_PATH).unwrap();
+    let font = FontRef::try_from_slice(&data).unwrap();
+    // `PxScale` is the height from descent to ascent, not the em size.
+    let scale = PxScale::from(FONT_SIZE * font.height_unscaled() / font.units_per_em().unwrap());
+    let scaled = font.as_scaled(scale);
+    let ascent = scaled.ascent().ceil();
+    let cell_height = (ascent - scaled.descent().floor()) as usize;
+    let chars: Vec<char> = CHAR_RANGES
+        .iter()
+        .flat_map(|(first, last)| *first..=*last)
+        .collect();
+    let cell_width = chars
+        .iter()
+        .map(|ch| scaled.h_advance(font.glyph_id(*ch)).ceil() as usize)
+        .max()
+        .unwrap_or(0);
+
+    let mut mask = vec![0u8; chars.len() * cell_width * cell_height];
+    let mut code = String::new();
+
+    writeln!(code, "pub const CELL_WIDTH: u64 = {cell_width};").unwrap();
+    writeln!(code, "pub const CELL_HEIGHT: u64 = {cell_height};").unwrap();
+    writeln!(code, "pub const ASCENT: u64 = {};", ascent as u64).unwrap();
+    writeln!(code, "pub const SIZE: f64 = {FONT_SIZE:.1};").unwrap();
+    write!(code, "pub const GLYPHS: [Glyph; {}] = [", chars.len()).unwrap();
+
+    for (index, ch) in chars.iter().enumerate() {
+        let glyph_id = font.glyph_id(*ch);
+        let glyph = glyph_id.with_scale_and_position(scale, point(0.0, ascent));
+        let cell = &mut mask[index * cell_width * cell_height..][..cell_width * cell_height];
+
+        if let Some(outlined) = font.outline_glyph(glyph) {
+            let bounds = outlined.px_bounds();
+
+            outlined.draw(|x, y, coverage| {
+                let x = bounds.min.x as i64 + x as i64;
+                let y = bounds.min.y as i64 + y as i64;
+
+                if coverage >= 0.5
+                    && (0..cell_width as i64).contains(&x)
+                    && (0..cell_height as i64).contains(&y)
+                {
+                    cell[y as usize * cell_width + x as usize] = u8::MAX;
+                }
+            });
+        }
+
+        write!(
+            code,
+            "Glyph::new({ch:?}, {}), ",
+            scaled.h_advance(glyph_id).round() as u64
+        )
+        .unwrap();
+    }
+
+    writeln!(code, "];").unwrap();
+
+    fs::write(out_dir.join("greybeard.bitmap"), mask).unwrap();
+    fs::write(out_dir.join("greybeard.rs"), code).unwrap();
+}
+
+fn main() {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    build_font(&out_dir);
+}
@@ -0,0 +1,94 @@
+use gpu::{Color, Point, Rect, TextAlign};
+
+/// Painter states a backend saves, deeper pushes are ignored together with their pops.
+pub const PAINTER_STACK_SIZE: usize = 8;
+
+/// CPU-side copy of the GPU painter state, `None` means the value is not known yet.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PainterState {
+    pub color: Option<Color>,
+    pub text_size: Option<f64>,
+    pub text_align: Option<TextAlign>,
+}
+
+/// Drawing operations of the console GPU. Text positions are the start of the baseline, moved
+/// according to the painter text align.
+pub trait RenderBackend {
+    /// Tells apart backends drawing on different devices, object ids are only valid on the
+    /// device that created them.
+    fn device_index(&self) -> usize {
+        0
+    }
+
+    /// Changes when the device loses all its objects, e.g. when it is reinitialized. Objects of
+    /// an older generation are gone, their owners have to create them again.
+    fn generation(&self) -> u64 {
+        0
+    }
+
+    fn bounds(&self) -> Rect;
+
+    fn width(&self) -> f64 {
+        self.bounds().width()
+    }
+
+    fn height(&self) -> f64 {
+        self.bounds().height()
+    }
+
+    /// `None` when the object could not be created.
+    fn create_text_object(&mut self, text: &str) -> Option<u64>;
+
+    /// `None` when the object could not be created.
+    fn create_image(&mut self, data: &[u8], width: u64, height: u64) -> Option<u64>;
+
+    fn delete_object(&mut self, object_id: u64);
+
+    /// Saves the painter state, must be paired with `pop_state`. Pushes past
+    /// `PAINTER_STACK_SIZE` don't panic, they save nothing and their pops restore nothing.
+    fn push_state(&mut self);
+
+    fn pop_state(&mut self);
+
+    fn set_painter_color(&mut self, color: Color);
+
+    fn get_painter_color(&mut self) -> Color;
+
+    fn set_painter_text_size(&mut self, size: f64);
+
+    fn get_painter_text_size(&mut self) -> f64;
+
+    fn set_painter_text_align(&mut self, align: TextAlign);
+
+    fn get_painter_text_align(&mut self) -> TextAlign;
+
+    fn draw_rect(&mut self, rect: Rect);
+
+    fn fill_screen(&mut self, color: Option<Color>) {
+        self.push_state();
+
+        if let Some(color) = color {
+            self.set_painter_color(color);
+        }
+
+        self.draw_rect(self.bounds());
+        self.pop_state();
+    }
+
+    fn draw_text(&mut self, object_id: u64, position: Point);
+
+    fn draw_string(&mut self, text: &str, position: Point);
+
+    fn measure_string(&mut self, text: &str) -> f64;
+
+    fn mesaure_text(&mut self, object_id: u64) -> f64;
+
+    fn draw_image(&mut self, object_id: u64, position: Point);
+
+    fn draw_image_rect(&mut self, object_id: u64, dst: Rect);
+
+    /// Draws the `src` part of the image scaled into `dst`, e.g. one sprite of an atlas.
+    fn draw_image_region(&mut self, object_id: u64, src: Rect, dst: Rect);
+
+    fn flip_buffers(&mut self);
+}
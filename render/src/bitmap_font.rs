@@ -0,0 +1,399 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+use gpu::{Color, Point, Rect, TextAlign};
+
+use crate::{RenderBackend, TextMetrics};
+
+const BYTES_PER_PIXEL: usize = 4;
+const FALLBACK_CHAR: char = '?';
+
+mod greybeard {
+    use super::Glyph;
+
+    include!(concat!(env!("OUT_DIR"), "/greybeard.rs"));
+}
+
+/// Greybeard 11px with Latin and Cyrillic letters, baked by the build script.
+pub const GREYBEARD: BitmapFont = BitmapFont {
+    glyphs: &greybeard::GLYPHS,
+    mask: include_bytes!(concat!(env!("OUT_DIR"), "/greybeard.bitmap")),
+    cell_width: greybeard::CELL_WIDTH,
+    cell_height: greybeard::CELL_HEIGHT,
+    ascent: greybeard::ASCENT,
+    size: greybeard::SIZE,
+};
+
+/// `f64::round` needs std.
+fn round(value: f64) -> f64 {
+    if value < 0.0 {
+        (value - 0.5) as i64 as f64
+    } else {
+        (value + 0.5) as i64 as f64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyph {
+    ch: char,
+    advance: u64,
+}
+
+impl Glyph {
+    pub const fn new(ch: char, advance: u64) -> Self {
+        Self { ch, advance }
+    }
+}
+
+/// Font rasterized into fixed size cells, one mask byte per pixel. Drawn without GPU text ops,
+/// either with rects (`draw_string`) or glyph images (`GlyphAtlas`), at integer scales only.
+#[derive(Debug, Clone, Copy)]
+pub struct BitmapFont {
+    glyphs: &'static [Glyph],
+    mask: &'static [u8],
+    cell_width: u64,
+    cell_height: u64,
+    ascent: u64,
+    size: f64,
+}
+
+impl BitmapFont {
+    pub fn cell_width(&self) -> u64 {
+        self.cell_width
+    }
+
+    pub fn cell_height(&self) -> u64 {
+        self.cell_height
+    }
+
+    /// Text size the font was rasterized at.
+    pub fn size(&self) -> f64 {
+        self.size
+    }
+
+    /// Integer scale closest to `text_size`, so pixels stay square.
+    pub fn scale(&self, text_size: f64) -> u64 {
+        u64::max(round(text_size / self.size) as u64, 1)
+    }
+
+    /// Index of the glyph for `ch`, characters missing from the font are drawn as "?".
+    fn glyph_index(&self, ch: char) -> usize {
+        self.glyphs
+            .binary_search_by_key(&ch, |glyph| glyph.ch)
+            .or_else(|_| {
+                self.glyphs
+                    .binary_search_by_key(&FALLBACK_CHAR, |glyph| glyph.ch)
+            })
+            .unwrap_or(0)
+    }
+
+    fn glyph_mask(&self, index: usize) -> &'static [u8] {
+        let cell_size = (self.cell_width * self.cell_height) as usize;
+
+        &self.mask[index * cell_size..(index + 1) * cell_size]
+    }
+
+    /// Extents of the glyph cells at `text_size`, unlike `TextMetrics::new` which is for GPU
+    /// text.
+    pub fn metrics(&self, width: f64, text_size: f64) -> TextMetrics {
+        let scale = self.scale(text_size) as f64;
+
+        TextMetrics {
+            width,
+            ascent: self.ascent as f64 * scale,
+            descent: (self.cell_height - self.ascent) as f64 * scale,
+        }
+    }
+
+    pub fn measure_string(&self, text: &str, text_size: f64) -> f64 {
+        let advance: u64 = text
+            .chars()
+            .map(|ch| self.glyphs[self.glyph_index(ch)].advance)
+            .sum();
+
+        (advance * self.scale(text_size)) as f64
+    }
+
+    /// Calls `draw` with the glyph index and its cell on the screen for every character, the
+    /// position is the start of the baseline as for GPU text.
+    fn layout(
+        &self,
+        text: &str,
+        position: Point,
+        text_size: f64,
+        align: TextAlign,
+        mut draw: impl FnMut(usize, Rect),
+    ) {
+        let scale = self.scale(text_size) as f64;
+        let width = self.measure_string(text, text_size);
+        let mut x = round(match align {
+            TextAlign::Left => position.x,
+            TextAlign::Center => position.x - width / 2.0,
+            TextAlign::Right => position.x - width,
+        });
+        let y = round(position.y - self.metrics(0.0, text_size).ascent);
+
+        for ch in text.chars() {
+            let index = self.glyph_index(ch);
+            let cell = Rect::new_from_position(
+                Point::new(x, y),
+                self.cell_width as f64 * scale,
+                self.cell_height as f64 * scale,
+            );
+
+            draw(index, cell);
+            x += self.glyphs[index].advance as f64 * scale;
+        }
+    }
+
+    /// Draws with the painter color using one rect per horizontal run of pixels, needs nothing
+    /// but `draw_rect` from the backend.
+    pub fn draw_string(
+        &self,
+        video: &mut impl RenderBackend,
+        text: &str,
+        position: Point,
+        text_size: f64,
+        align: TextAlign,
+    ) {
+        let scale = self.scale(text_size) as f64;
+        let cell_width = self.cell_width as usize;
+
+        self.layout(text, position, text_size, align, |index, cell| {
+            let origin = cell.position();
+
+            for (row, pixels) in self.glyph_mask(index).chunks(cell_width).enumerate() {
+                let mut column = 0;
+
+                while column < cell_width {
+                    if pixels[column] == 0 {
+                        column += 1;
+                        continue;
+                    }
+
+                    let start = column;
+
+                    while column < cell_width && pixels[column] != 0 {
+                        column += 1;
+                    }
+
+                    let left = origin.x + start as f64 * scale;
+                    let top = origin.y + row as f64 * scale;
+
+                    video.draw_rect(Rect::new(
+                        left,
+                        top,
+                        left + (column - start) as f64 * scale,
+                        top + scale,
+                    ));
+                }
+            }
+        });
+    }
+}
+
+/// Uploads glyphs of a `BitmapFont` as images on first use and draws text with them, one image
+/// op per character. The color is baked into the images.
+#[derive(Debug)]
+pub struct GlyphAtlas {
+    font: BitmapFont,
+    color: Color,
+    objects: BTreeMap<usize, u64>,
+    /// `RenderBackend::generation` the glyphs were uploaded in.
+    generation: u64,
+}
+
+impl GlyphAtlas {
+    pub fn new(font: BitmapFont, color: Color) -> Self {
+        Self {
+            font,
+            color,
+            objects: BTreeMap::new(),
+            generation: 0,
+        }
+    }
+
+    pub fn font(&self) -> &BitmapFont {
+        &self.font
+    }
+
+    pub fn objects_count(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Uploads the glyph on first use, a failed upload is tried again on the next draw.
+    fn glyph_object(&mut self, index: usize, video: &mut impl RenderBackend) -> Option<u64> {
+        // Glyphs lost on a reinit are uploaded again.
+        if self.generation != video.generation() {
+            self.objects.clear();
+            self.generation = video.generation();
+        }
+
+        if let Some(object_id) = self.objects.get(&index) {
+            return Some(*object_id);
+        }
+
+        let mut data = Vec::with_capacity(self.font.glyph_mask(index).len() * BYTES_PER_PIXEL);
+
+        for coverage in self.font.glyph_mask(index) {
+            let alpha = ((*coverage as u16 * self.color.a as u16) / u8::MAX as u16) as u8;
+
+            data.extend_from_slice(&[self.color.r, self.color.g, self.color.b, alpha]);
+        }
+
+        let object_id = video.create_image(&data, self.font.cell_width, self.font.cell_height)?;
+
+        self.objects.insert(index, object_id);
+
+        Some(object_id)
+    }
+
+    pub fn draw_string(
+        &mut self,
+        video: &mut impl RenderBackend,
+        text: &str,
+        position: Point,
+        text_size: f64,
+        align: TextAlign,
+    ) {
+        let mut cells = Vec::new();
+
+        self.font
+            .layout(text, position, text_size, align, |index, cell| {
+                cells.push((index, cell))
+            });
+
+        for (index, cell) in cells {
+            if self
+                .font
+                .glyph_mask(index)
+                .iter()
+                .all(|coverage| *coverage == 0)
+            {
+                continue;
+            }
+
+            if let Some(object_id) = self.glyph_object(index, video) {
+                video.draw_image_rect(object_id, cell);
+            }
+        }
+    }
+
+    /// Deletes the uploaded glyphs, the atlas can still be used afterwards.
+    pub fn release(&mut self, video: &mut impl RenderBackend) {
+        let objects = core::mem::take(&mut self.objects);
+
+        if self.generation != video.generation() {
+            return;
+        }
+
+        for (_, object_id) in objects {
+            video.delete_object(object_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BackendCall, MockBackend};
+
+    fn creates(video: &MockBackend) -> usize {
+        video.count(|call| matches!(call, BackendCall::CreateImage { .. }))
+    }
+
+    fn draws(video: &MockBackend) -> usize {
+        video.count(|call| matches!(call, BackendCall::DrawImageRect { .. }))
+    }
+
+    fn draw(atlas: &mut GlyphAtlas, video: &mut MockBackend, text: &str) {
+        atlas.draw_string(video, text, Point::zero(), 12.0, TextAlign::Left);
+    }
+
+    #[test]
+    fn metrics_match_the_cells() {
+        let metrics = GREYBEARD.metrics(10.0, GREYBEARD.size() * 2.0);
+
+        assert_eq!(metrics.height(), (GREYBEARD.cell_height() * 2) as f64);
+        assert_eq!(metrics.ascent, (greybeard::ASCENT * 2) as f64);
+    }
+
+    #[test]
+    fn cells_hang_from_the_baseline() {
+        let mut video = MockBackend::new(100.0, 50.0);
+        let mut atlas = GlyphAtlas::new(GREYBEARD, Color::white());
+        let baseline = Point::new(0.0, 20.0);
+
+        atlas.draw_string(&mut video, "A", baseline, GREYBEARD.size(), TextAlign::Left);
+
+        let cell = Rect::new_from_position(
+            Point::new(0.0, 20.0 - greybeard::ASCENT as f64),
+            GREYBEARD.cell_width() as f64,
+            GREYBEARD.cell_height() as f64,
+        );
+
+        assert_eq!(
+            video.count(|call| *call
+                == BackendCall::DrawImageRect {
+                    object_id: 1,
+                    dst: cell
+                }),
+            1
+        );
+    }
+
+    #[test]
+    fn uploads_every_glyph_once() {
+        let mut video = MockBackend::new(100.0, 50.0);
+        let mut atlas = GlyphAtlas::new(GREYBEARD, Color::white());
+
+        draw(&mut atlas, &mut video, "ABA");
+        draw(&mut atlas, &mut video, "BA");
+
+        assert_eq!(creates(&video), 2);
+        assert_eq!(draws(&video), 5);
+        assert_eq!(atlas.objects_count(), 2);
+    }
+
+    #[test]
+    fn skips_blank_glyphs() {
+        let mut video = MockBackend::new(100.0, 50.0);
+        let mut atlas = GlyphAtlas::new(GREYBEARD, Color::white());
+
+        draw(&mut atlas, &mut video, "  ");
+
+        assert!(video.calls().is_empty());
+    }
+
+    #[test]
+    fn release_deletes_glyphs() {
+        let mut video = MockBackend::new(100.0, 50.0);
+        let mut atlas = GlyphAtlas::new(GREYBEARD, Color::white());
+
+        draw(&mut atlas, &mut video, "AB");
+        atlas.release(&mut video);
+
+        assert_eq!(
+            video.count(|call| matches!(call, BackendCall::DeleteObject { .. })),
+            2
+        );
+        assert_eq!(atlas.objects_count(), 0);
+    }
+
+    #[test]
+    fn uploads_again_after_reinit() {
+        let mut video = MockBackend::new(100.0, 50.0);
+        let mut atlas = GlyphAtlas::new(GREYBEARD, Color::white());
+
+        draw(&mut atlas, &mut video, "A");
+        video.reinit();
+        atlas.release(&mut video);
+
+        assert_eq!(
+            video.count(|call| matches!(call, BackendCall::DeleteObject { .. })),
+            0
+        );
+
+        draw(&mut atlas, &mut video, "A");
+
+        assert_eq!(creates(&video), 2);
+    }
+}
@@ -0,0 +1,395 @@
+use gpu::Color;
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color {
+        r,
+        g,
+        b,
+        a: u8::MAX,
+    }
+}
+
+fn channel(value: f64) -> u8 {
+    (value.clamp(0.0, 255.0) + 0.5) as u8
+}
+
+fn lerp_channel(from: u8, to: u8, t: f64) -> u8 {
+    channel(from as f64 + (to as f64 - from as f64) * t)
+}
+
+/// Arithmetic on `gpu::Color`, channels are clamped to 0..=255.
+pub trait ColorExt {
+    fn with_alpha(self, a: u8) -> Color;
+
+    fn to_rgba(self) -> [u8; 4];
+
+    /// Color `t` of the way from `self` to `other`, `t` is clamped to 0.0..=1.0.
+    fn lerp(self, other: Color, t: f64) -> Color;
+
+    /// `self` drawn over `under` with its alpha.
+    fn blend(self, under: Color) -> Color;
+
+    /// Channels multiplied by the ones of `tint`, white leaves the color as is.
+    fn tint(self, tint: Color) -> Color;
+
+    fn to_hsv(self) -> Hsv;
+}
+
+impl ColorExt for Color {
+    fn with_alpha(mut self, a: u8) -> Color {
+        self.a = a;
+
+        self
+    }
+
+    fn to_rgba(self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
+    fn lerp(self, other: Color, t: f64) -> Color {
+        let t = t.clamp(0.0, 1.0);
+
+        Color {
+            r: lerp_channel(self.r, other.r, t),
+            g: lerp_channel(self.g, other.g, t),
+            b: lerp_channel(self.b, other.b, t),
+            a: lerp_channel(self.a, other.a, t),
+        }
+    }
+
+    fn blend(self, under: Color) -> Color {
+        let alpha = self.a as f64 / 255.0;
+        let under_alpha = under.a as f64 / 255.0 * (1.0 - alpha);
+        let out_alpha = alpha + under_alpha;
+
+        if out_alpha <= 0.0 {
+            return Color { a: 0, ..under };
+        }
+
+        let mix = |over: u8, under: u8| {
+            channel((over as f64 * alpha + under as f64 * under_alpha) / out_alpha)
+        };
+
+        Color {
+            r: mix(self.r, under.r),
+            g: mix(self.g, under.g),
+            b: mix(self.b, under.b),
+            a: channel(out_alpha * 255.0),
+        }
+    }
+
+    fn tint(self, tint: Color) -> Color {
+        let multiply = |value: u8, tint: u8| ((value as u16 * tint as u16 + 127) / 255) as u8;
+
+        Color {
+            r: multiply(self.r, tint.r),
+            g: multiply(self.g, tint.g),
+            b: multiply(self.b, tint.b),
+            a: multiply(self.a, tint.a),
+        }
+    }
+
+    fn to_hsv(self) -> Hsv {
+        let (r, g, b) = (
+            self.r as f64 / 255.0,
+            self.g as f64 / 255.0,
+            self.b as f64 / 255.0,
+        );
+        let max = f64::max(r, f64::max(g, b));
+        let min = f64::min(r, f64::min(g, b));
+        let delta = max - min;
+
+        let h = if delta <= 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+
+        Hsv {
+            h: if h < 0.0 { h + 360.0 } else { h },
+            s: if max > 0.0 { delta / max } else { 0.0 },
+            v: max,
+            a: self.a,
+        }
+    }
+}
+
+/// Hue in degrees, saturation and value from 0.0 to 1.0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub h: f64,
+    pub s: f64,
+    pub v: f64,
+    pub a: u8,
+}
+
+impl Hsv {
+    pub const fn new(h: f64, s: f64, v: f64) -> Self {
+        Self {
+            h,
+            s,
+            v,
+            a: u8::MAX,
+        }
+    }
+
+    pub fn with_hue(mut self, h: f64) -> Self {
+        self.h = h;
+
+        self
+    }
+
+    pub fn to_color(self) -> Color {
+        let h = self.h % 360.0;
+        let h = if h < 0.0 { h + 360.0 } else { h } / 60.0;
+        let s = self.s.clamp(0.0, 1.0);
+        let v = self.v.clamp(0.0, 1.0);
+        let sector = h as u8;
+        let f = h - sector as f64;
+        let p = v * (1.0 - s);
+        let q = v * (1.0 - s * f);
+        let t = v * (1.0 - s * (1.0 - f));
+
+        let (r, g, b) = match sector {
+            0 => (v, t, p),
+            1 => (q, v, p),
+            2 => (p, v, t),
+            3 => (p, q, v),
+            4 => (t, p, v),
+            _ => (v, p, q),
+        };
+
+        Color {
+            r: channel(r * 255.0),
+            g: channel(g * 255.0),
+            b: channel(b * 255.0),
+            a: self.a,
+        }
+    }
+}
+
+impl From<Hsv> for Color {
+    fn from(hsv: Hsv) -> Self {
+        hsv.to_color()
+    }
+}
+
+/// Colors at positions from 0.0 to 1.0, `at` interpolates between the two nearest stops.
+/// Stops must be sorted by position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gradient<'a> {
+    stops: &'a [(f64, Color)],
+}
+
+impl<'a> Gradient<'a> {
+    pub const fn new(stops: &'a [(f64, Color)]) -> Self {
+        Self { stops }
+    }
+
+    pub fn stops(&self) -> &'a [(f64, Color)] {
+        self.stops
+    }
+
+    pub fn at(&self, position: f64) -> Color {
+        let Some(&(first_position, first)) = self.stops.first() else {
+            return Color::black();
+        };
+
+        if position <= first_position {
+            return first;
+        }
+
+        for pair in self.stops.windows(2) {
+            let ((from_position, from), (to_position, to)) = (pair[0], pair[1]);
+
+            if position <= to_position {
+                return match to_position - from_position {
+                    length if length > 0.0 => from.lerp(to, (position - from_position) / length),
+                    _ => to,
+                };
+            }
+        }
+
+        self.stops[self.stops.len() - 1].1
+    }
+
+    /// `count` colors evenly spread over the gradient, e.g. one per row.
+    pub fn steps(&self, count: usize) -> impl Iterator<Item = Color> + '_ {
+        (0..count).map(move |index| match count {
+            1 => self.at(0.0),
+            _ => self.at(index as f64 / (count - 1) as f64),
+        })
+    }
+}
+
+/// Multiplies every pixel of RGBA `data` by `tint`, for coloring white bitmaps at load time.
+pub fn tint_rgba(data: &mut [u8], tint: Color) {
+    for pixel in data.chunks_exact_mut(4) {
+        let color = Color {
+            r: pixel[0],
+            g: pixel[1],
+            b: pixel[2],
+            a: pixel[3],
+        };
+
+        pixel.copy_from_slice(&color.tint(tint).to_rgba());
+    }
+}
+
+pub mod palette {
+    /// Colors of the cabinet cellophane overlays.
+    pub mod arcade {
+        use super::super::{rgb, Gradient};
+        use gpu::Color;
+
+        pub const WHITE: Color = rgb(0xff, 0xff, 0xff);
+        pub const RED: Color = rgb(0xf8, 0x3b, 0x3a);
+        pub const ORANGE: Color = rgb(0xfb, 0x91, 0x2b);
+        pub const YELLOW: Color = rgb(0xf3, 0xe7, 0x2c);
+        pub const GREEN: Color = rgb(0x20, 0xff, 0x20);
+        pub const CYAN: Color = rgb(0x4f, 0xe8, 0xf0);
+        pub const MAGENTA: Color = rgb(0xdc, 0x3f, 0xd3);
+
+        /// Invader rows from the top one down.
+        pub const ROWS: Gradient<'static> = Gradient::new(&[
+            (0.0, MAGENTA),
+            (0.25, CYAN),
+            (0.5, GREEN),
+            (0.75, YELLOW),
+            (1.0, ORANGE),
+        ]);
+    }
+
+    /// Colors of the medical devices, `SEVERITY` goes from healthy at 0.0 to critical at 1.0.
+    pub mod medical {
+        use super::super::{rgb, Gradient};
+        use gpu::Color;
+
+        pub const OK: Color = rgb(0x2e, 0xcc, 0x40);
+        pub const MINOR: Color = rgb(0xff, 0xdc, 0x00);
+        pub const SEVERE: Color = rgb(0xff, 0x85, 0x1b);
+        pub const CRITICAL: Color = rgb(0xff, 0x41, 0x36);
+        pub const INFO: Color = rgb(0x7f, 0xdb, 0xff);
+
+        pub const SEVERITY: Gradient<'static> =
+            Gradient::new(&[(0.0, OK), (0.25, MINOR), (0.6, SEVERE), (1.0, CRITICAL)]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lerp_clamps_t() {
+        let (black, white) = (Color::black(), Color::white());
+
+        assert_eq!(black.lerp(white, 0.0), black);
+        assert_eq!(black.lerp(white, 1.0), white);
+        assert_eq!(black.lerp(white, 0.5), rgb(128, 128, 128));
+        assert_eq!(black.lerp(white, -1.0), black);
+        assert_eq!(black.lerp(white, 2.0), white);
+    }
+
+    #[test]
+    fn lerp_includes_alpha() {
+        let from = rgb(0, 0, 0).with_alpha(0);
+
+        assert_eq!(
+            from.lerp(rgb(200, 100, 50), 0.5),
+            Color::new(100, 50, 25, 128)
+        );
+    }
+
+    #[test]
+    fn blend_opaque_and_transparent() {
+        let (red, blue) = (rgb(255, 0, 0), rgb(0, 0, 255));
+
+        assert_eq!(red.blend(blue), red);
+        assert_eq!(red.with_alpha(0).blend(blue), blue);
+        assert_eq!(red.with_alpha(0).blend(blue.with_alpha(0)).a, 0);
+    }
+
+    #[test]
+    fn blend_half_alpha() {
+        let over = rgb(255, 0, 0).with_alpha(128);
+
+        assert_eq!(over.blend(rgb(0, 0, 255)), Color::new(128, 0, 127, 255));
+        assert_eq!(over.blend(Color::black().with_alpha(0)), over);
+    }
+
+    #[test]
+    fn hsv_of_primaries() {
+        assert_eq!(rgb(255, 0, 0).to_hsv(), Hsv::new(0.0, 1.0, 1.0));
+        assert_eq!(rgb(0, 255, 0).to_hsv(), Hsv::new(120.0, 1.0, 1.0));
+        assert_eq!(rgb(0, 0, 255).to_hsv(), Hsv::new(240.0, 1.0, 1.0));
+        assert_eq!(Color::black().to_hsv(), Hsv::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn hsv_round_trip() {
+        let colors = [
+            palette::arcade::RED,
+            palette::arcade::ORANGE,
+            palette::arcade::YELLOW,
+            palette::arcade::GREEN,
+            palette::arcade::CYAN,
+            palette::arcade::MAGENTA,
+            palette::medical::INFO,
+            rgb(0x80, 0x80, 0x80),
+            rgb(1, 2, 3).with_alpha(77),
+        ];
+
+        for color in colors {
+            assert_eq!(color.to_hsv().to_color(), color);
+        }
+    }
+
+    #[test]
+    fn hsv_wraps_hue() {
+        let blue = Hsv::new(240.0, 1.0, 1.0).to_color();
+
+        assert_eq!(Hsv::new(-120.0, 1.0, 1.0).to_color(), blue);
+        assert_eq!(Hsv::new(600.0, 1.0, 1.0).to_color(), blue);
+    }
+
+    #[test]
+    fn gradient_at_stops_and_between() {
+        let (black, white) = (Color::black(), Color::white());
+        let stops = [(0.25, black), (0.75, white)];
+        let gradient = Gradient::new(&stops);
+
+        assert_eq!(gradient.at(0.0), black);
+        assert_eq!(gradient.at(0.25), black);
+        assert_eq!(gradient.at(0.5), rgb(128, 128, 128));
+        assert_eq!(gradient.at(0.75), white);
+        assert_eq!(gradient.at(1.0), white);
+    }
+
+    #[test]
+    fn gradient_at_without_stops() {
+        assert_eq!(Gradient::new(&[]).at(0.5), Color::black());
+    }
+
+    #[test]
+    fn gradient_at_same_position() {
+        let stops = [(0.5, Color::black()), (0.5, Color::white())];
+        let gradient = Gradient::new(&stops);
+
+        assert_eq!(gradient.at(0.5), Color::black());
+        assert_eq!(gradient.at(0.6), Color::white());
+    }
+
+    #[test]
+    fn gradient_steps_hit_the_stops() {
+        let rows = palette::arcade::ROWS;
+        let stops = rows.stops().iter().map(|(_, color)| *color);
+
+        assert!(rows.steps(rows.stops().len()).eq(stops));
+        assert!(rows.steps(1).eq([palette::arcade::MAGENTA]));
+    }
+}
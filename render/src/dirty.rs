@@ -0,0 +1,246 @@
+use alloc::vec::Vec;
+use gpu::Rect;
+
+const MAX_DIRTY_RECTS: usize = 16;
+
+fn right(rect: &Rect) -> f64 {
+    rect.position().x + rect.width()
+}
+
+fn bottom(rect: &Rect) -> f64 {
+    rect.position().y + rect.height()
+}
+
+fn intersects(a: &Rect, b: &Rect) -> bool {
+    a.position().x < right(b)
+        && b.position().x < right(a)
+        && a.position().y < bottom(b)
+        && b.position().y < bottom(a)
+}
+
+fn intersection(a: &Rect, b: &Rect) -> Option<Rect> {
+    if !intersects(a, b) {
+        return None;
+    }
+
+    Some(Rect::new(
+        f64::max(a.position().x, b.position().x),
+        f64::max(a.position().y, b.position().y),
+        f64::min(right(a), right(b)),
+        f64::min(bottom(a), bottom(b)),
+    ))
+}
+
+fn union(a: &Rect, b: &Rect) -> Rect {
+    Rect::new(
+        f64::min(a.position().x, b.position().x),
+        f64::min(a.position().y, b.position().y),
+        f64::max(right(a), right(b)),
+        f64::max(bottom(a), bottom(b)),
+    )
+}
+
+fn contains(outer: &Rect, inner: &Rect) -> bool {
+    outer.position().x <= inner.position().x
+        && outer.position().y <= inner.position().y
+        && right(outer) >= right(inner)
+        && bottom(outer) >= bottom(inner)
+}
+
+/// A set of screen regions that must be redrawn. Overlapping regions are merged and the set
+/// collapses into its bounding box once it grows past `MAX_DIRTY_RECTS`.
+#[derive(Debug, Clone, Default)]
+pub struct DirtyRegions {
+    rects: Vec<Rect>,
+}
+
+impl DirtyRegions {
+    pub const fn new() -> Self {
+        Self { rects: Vec::new() }
+    }
+
+    pub fn add(&mut self, rect: Rect) -> bool {
+        if rect.width() <= 0.0 || rect.height() <= 0.0 {
+            return false;
+        }
+
+        if self.rects.iter().any(|dirty| contains(dirty, &rect)) {
+            return false;
+        }
+
+        let mut rect = rect;
+
+        while let Some(index) = self.rects.iter().position(|dirty| intersects(dirty, &rect)) {
+            rect = union(&rect, &self.rects.swap_remove(index));
+        }
+
+        self.rects.push(rect);
+
+        if self.rects.len() > MAX_DIRTY_RECTS {
+            let bounding = self
+                .rects
+                .iter()
+                .skip(1)
+                .fold(self.rects[0], |acc, rect| union(&acc, rect));
+
+            self.rects.clear();
+            self.rects.push(bounding);
+        }
+
+        true
+    }
+
+    pub fn extend(&mut self, other: &DirtyRegions) {
+        for rect in other.rects() {
+            self.add(*rect);
+        }
+    }
+
+    pub fn intersects(&self, rect: &Rect) -> bool {
+        self.rects.iter().any(|dirty| intersects(dirty, rect))
+    }
+
+    /// Parts of `rect` inside the regions, one per region it overlaps.
+    pub fn clip<'a>(&'a self, rect: &'a Rect) -> impl Iterator<Item = Rect> + 'a {
+        self.rects
+            .iter()
+            .filter_map(|dirty| intersection(rect, dirty))
+    }
+
+    pub fn rects(&self) -> &[Rect] {
+        &self.rects
+    }
+
+    pub fn clear(&mut self) {
+        self.rects.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64, side: f64) -> Rect {
+        Rect::new(x, y, x + side, y + side)
+    }
+
+    #[test]
+    fn skips_empty_rects() {
+        let mut regions = DirtyRegions::new();
+
+        assert!(!regions.add(Rect::new(10.0, 10.0, 10.0, 20.0)));
+        assert!(!regions.add(Rect::new(10.0, 10.0, 20.0, 10.0)));
+        assert!(regions.rects().is_empty());
+    }
+
+    #[test]
+    fn keeps_disjoint_rects() {
+        let mut regions = DirtyRegions::new();
+
+        assert!(regions.add(square(0.0, 0.0, 10.0)));
+        assert!(regions.add(square(20.0, 0.0, 10.0)));
+        assert_eq!(regions.rects().len(), 2);
+    }
+
+    #[test]
+    fn touching_rects_stay_apart() {
+        let mut regions = DirtyRegions::new();
+
+        regions.add(square(0.0, 0.0, 10.0));
+        regions.add(square(10.0, 0.0, 10.0));
+
+        assert_eq!(regions.rects().len(), 2);
+    }
+
+    #[test]
+    fn merges_overlapping_rects() {
+        let mut regions = DirtyRegions::new();
+
+        regions.add(square(0.0, 0.0, 10.0));
+        regions.add(square(5.0, 5.0, 10.0));
+
+        assert_eq!(regions.rects(), &[Rect::new(0.0, 0.0, 15.0, 15.0)]);
+    }
+
+    #[test]
+    fn merges_rects_bridged_by_a_new_one() {
+        let mut regions = DirtyRegions::new();
+
+        regions.add(square(0.0, 0.0, 10.0));
+        regions.add(square(20.0, 0.0, 10.0));
+        regions.add(Rect::new(5.0, 2.0, 25.0, 4.0));
+
+        assert_eq!(regions.rects(), &[Rect::new(0.0, 0.0, 30.0, 10.0)]);
+    }
+
+    #[test]
+    fn ignores_covered_rects() {
+        let mut regions = DirtyRegions::new();
+
+        regions.add(square(0.0, 0.0, 10.0));
+
+        assert!(!regions.add(square(2.0, 2.0, 5.0)));
+        assert!(!regions.add(square(0.0, 0.0, 10.0)));
+        assert_eq!(regions.rects(), &[square(0.0, 0.0, 10.0)]);
+    }
+
+    #[test]
+    fn intersects_and_clips() {
+        let mut regions = DirtyRegions::new();
+
+        regions.add(square(0.0, 0.0, 10.0));
+        regions.add(square(20.0, 0.0, 10.0));
+
+        let rect = Rect::new(5.0, 5.0, 25.0, 20.0);
+        let mut clipped = regions.clip(&rect);
+
+        assert!(regions.intersects(&rect));
+        assert!(!regions.intersects(&square(10.0, 10.0, 5.0)));
+        assert_eq!(clipped.next(), Some(Rect::new(5.0, 5.0, 10.0, 10.0)));
+        assert_eq!(clipped.next(), Some(Rect::new(20.0, 5.0, 25.0, 10.0)));
+        assert_eq!(clipped.next(), None);
+    }
+
+    #[test]
+    fn collapses_into_bounding_box() {
+        let mut regions = DirtyRegions::new();
+
+        for index in 0..MAX_DIRTY_RECTS {
+            regions.add(square(index as f64 * 20.0, 0.0, 10.0));
+        }
+
+        assert_eq!(regions.rects().len(), MAX_DIRTY_RECTS);
+
+        regions.add(square(0.0, 100.0, 10.0));
+
+        assert_eq!(
+            regions.rects(),
+            &[Rect::new(
+                0.0,
+                0.0,
+                (MAX_DIRTY_RECTS - 1) as f64 * 20.0 + 10.0,
+                110.0
+            )]
+        );
+        assert!(regions.intersects(&square(50.0, 50.0, 1.0)));
+    }
+
+    #[test]
+    fn extends_and_clears() {
+        let (mut regions, mut other) = (DirtyRegions::new(), DirtyRegions::new());
+
+        regions.add(square(0.0, 0.0, 10.0));
+        other.add(square(5.0, 0.0, 10.0));
+        other.add(square(40.0, 0.0, 10.0));
+        regions.extend(&other);
+
+        assert_eq!(
+            regions.rects(),
+            &[Rect::new(0.0, 0.0, 15.0, 10.0), square(40.0, 0.0, 10.0)]
+        );
+
+        regions.clear();
+
+        assert!(regions.rects().is_empty());
+    }
+}
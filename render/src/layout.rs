@@ -0,0 +1,471 @@
+use alloc::{string::String, vec::Vec};
+use gpu::{Point, Rect, TextAlign};
+
+use crate::{BitmapFont, RenderBackend, TextMetrics};
+
+const ELLIPSIS: &str = "...";
+
+/// What happens to the lines that don't fit into the box height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    Clip,
+    /// Drops the rest and ends the last visible line with "...".
+    Ellipsis,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerticalAlign {
+    Top,
+    Center,
+    Bottom,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line<'a> {
+    pub text: &'a str,
+    pub width: f64,
+    pub ellipsis: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout<'a> {
+    lines: Vec<Line<'a>>,
+    line_height: f64,
+    overflowed: bool,
+}
+
+impl<'a> TextLayout<'a> {
+    pub fn lines(&self) -> &[Line<'a>] {
+        &self.lines
+    }
+
+    /// Whether some of the text didn't fit vertically and was clipped or ellipsized.
+    pub fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+
+    pub fn height(&self) -> f64 {
+        self.lines.len() as f64 * self.line_height
+    }
+
+    pub fn width(&self) -> f64 {
+        self.lines.iter().map(|line| line.width).fold(0.0, f64::max)
+    }
+}
+
+/// Rectangle that text is wrapped into. Lines break on spaces and `\n`, words wider than the box
+/// are split between characters.
+#[derive(Debug, Clone, Copy)]
+pub struct TextBox {
+    rect: Rect,
+    text_size: f64,
+    line_spacing: f64,
+    align: TextAlign,
+    vertical_align: VerticalAlign,
+    overflow: Overflow,
+}
+
+impl TextBox {
+    pub fn new(rect: Rect, text_size: f64) -> Self {
+        Self {
+            rect,
+            text_size,
+            line_spacing: 0.0,
+            align: TextAlign::Left,
+            vertical_align: VerticalAlign::Top,
+            overflow: Overflow::Clip,
+        }
+    }
+
+    pub fn with_line_spacing(mut self, spacing: f64) -> Self {
+        self.line_spacing = spacing;
+
+        self
+    }
+
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+
+        self
+    }
+
+    pub fn with_vertical_align(mut self, align: VerticalAlign) -> Self {
+        self.vertical_align = align;
+
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+
+        self
+    }
+
+    pub fn set_rect(&mut self, rect: Rect) {
+        self.rect = rect;
+    }
+
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn text_size(&self) -> f64 {
+        self.text_size
+    }
+
+    pub fn line_height(&self) -> f64 {
+        self.text_size + self.line_spacing
+    }
+
+    pub fn max_lines(&self) -> usize {
+        let lines = (self.rect.height() + self.line_spacing) / self.line_height();
+
+        f64::max(lines, 0.0) as usize
+    }
+
+    /// Wraps `text` measuring it with `measure`, which must use the box text size. Works with any
+    /// renderer, `draw` does the same for a `RenderBackend`.
+    pub fn layout<'a>(
+        &self,
+        text: &'a str,
+        mut measure: impl FnMut(&str) -> f64,
+    ) -> TextLayout<'a> {
+        let max_lines = self.max_lines();
+        let width = self.rect.width();
+        let mut lines = Vec::new();
+        let mut overflowed = false;
+
+        'paragraphs: for paragraph in text.split('\n') {
+            let mut start = 0;
+
+            loop {
+                let rest = &paragraph[start..];
+
+                start += rest.len() - rest.trim_start_matches(' ').len();
+
+                // Blank paragraphs still take a line.
+                if start == paragraph.len() && !paragraph.trim_matches(' ').is_empty() {
+                    break;
+                }
+
+                if lines.len() == max_lines {
+                    overflowed = true;
+
+                    break 'paragraphs;
+                }
+
+                let (end, line_width) = wrap_line(paragraph, start, width, &mut measure);
+
+                lines.push(Line {
+                    text: paragraph[start..end].trim_end_matches(' '),
+                    width: line_width,
+                    ellipsis: false,
+                });
+
+                start = end;
+
+                if start == paragraph.len() {
+                    break;
+                }
+            }
+        }
+
+        if overflowed && self.overflow == Overflow::Ellipsis {
+            if let Some(line) = lines.last_mut() {
+                ellipsize(line, width, &mut measure);
+            }
+        }
+
+        TextLayout {
+            lines,
+            line_height: self.line_height(),
+            overflowed,
+        }
+    }
+
+    /// Lays out and draws `text` with the current painter color, returns the layout to check for
+    /// overflow.
+    pub fn draw<'a>(&self, text: &'a str, video: &mut impl RenderBackend) -> TextLayout<'a> {
+        video.push_state();
+        video.set_painter_text_size(self.text_size);
+        video.set_painter_text_align(self.align);
+
+        let layout = self.layout(text, |line| video.measure_string(line));
+
+        let ascent = TextMetrics::new(0.0, self.text_size).ascent;
+
+        self.draw_lines(&layout, ascent, |line, position| {
+            video.draw_string(line, position)
+        });
+        video.pop_state();
+
+        layout
+    }
+
+    /// Same as `draw`, but with a bitmap font, for when GPU text ops can't be used.
+    pub fn draw_bitmap<'a>(
+        &self,
+        text: &'a str,
+        font: &BitmapFont,
+        video: &mut impl RenderBackend,
+    ) -> TextLayout<'a> {
+        let layout = self.layout(text, |line| font.measure_string(line, self.text_size));
+
+        let ascent = font.metrics(0.0, self.text_size).ascent;
+
+        self.draw_lines(&layout, ascent, |line, position| {
+            font.draw_string(video, line, position, self.text_size, self.align)
+        });
+
+        layout
+    }
+
+    /// `ascent` puts the baseline of every line below its top.
+    fn draw_lines(&self, layout: &TextLayout, ascent: f64, mut draw: impl FnMut(&str, Point)) {
+        let x = match self.align {
+            TextAlign::Left => self.rect.position().x,
+            TextAlign::Center => self.rect.hcenter(),
+            TextAlign::Right => self.rect.position().x + self.rect.width(),
+        };
+        let free_space = self.rect.height() - (layout.height() - self.line_spacing);
+        let mut y = self.rect.position().y
+            + match self.vertical_align {
+                VerticalAlign::Top => 0.0,
+                VerticalAlign::Center => free_space / 2.0,
+                VerticalAlign::Bottom => free_space,
+            };
+        let mut ellipsized = String::new();
+
+        for line in layout.lines() {
+            let position = Point::new(x, y + ascent);
+
+            if line.ellipsis {
+                ellipsized.clear();
+                ellipsized.push_str(line.text);
+                ellipsized.push_str(ELLIPSIS);
+                draw(&ellipsized, position);
+            } else if !line.text.is_empty() {
+                draw(line.text, position);
+            }
+
+            y += self.line_height();
+        }
+    }
+}
+
+/// Finds where the line starting at `start` ends: after the last word that fits, or after the
+/// last fitting character when the first word alone is too wide.
+fn wrap_line(
+    paragraph: &str,
+    start: usize,
+    width: f64,
+    measure: &mut impl FnMut(&str) -> f64,
+) -> (usize, f64) {
+    let rest = &paragraph[start..];
+    let mut end = start;
+    let mut line_width = 0.0;
+    let breaks = rest
+        .match_indices(' ')
+        .map(|(index, _)| index)
+        .chain(Some(rest.len()));
+
+    for index in breaks {
+        let candidate = rest[..index].trim_end_matches(' ');
+
+        if candidate.is_empty() {
+            continue;
+        }
+
+        let candidate_width = measure(candidate);
+
+        if candidate_width > width {
+            break;
+        }
+
+        end = start + index;
+        line_width = candidate_width;
+    }
+
+    if end != start {
+        return (end, line_width);
+    }
+
+    for (index, ch) in rest.char_indices() {
+        let candidate_end = index + ch.len_utf8();
+        let candidate_width = measure(&rest[..candidate_end]);
+
+        if candidate_width > width && index != 0 {
+            break;
+        }
+
+        end = start + candidate_end;
+        line_width = candidate_width;
+    }
+
+    (end, line_width)
+}
+
+fn ellipsize(line: &mut Line<'_>, width: f64, measure: &mut impl FnMut(&str) -> f64) {
+    let mut text = String::from(line.text);
+
+    loop {
+        let len = text.len();
+
+        text.push_str(ELLIPSIS);
+
+        let text_width = measure(&text);
+
+        text.truncate(len);
+
+        if text_width <= width || text.is_empty() {
+            line.text = &line.text[..len];
+            line.width = text_width;
+            line.ellipsis = true;
+
+            return;
+        }
+
+        text.pop();
+        let trimmed = text.trim_end_matches(' ').len();
+        text.truncate(trimmed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::{BackendCall, MockBackend};
+
+    /// Mock chars are 6 px wide at this size.
+    const TEXT_SIZE: f64 = 12.0;
+
+    fn text_box(chars: f64, lines: f64) -> TextBox {
+        TextBox::new(
+            Rect::new(0.0, 0.0, chars * 6.0, lines * TEXT_SIZE),
+            TEXT_SIZE,
+        )
+    }
+
+    fn layout<'a>(text_box: TextBox, text: &'a str) -> TextLayout<'a> {
+        let mut video = MockBackend::new(100.0, 100.0);
+
+        text_box.layout(text, |line| video.measure_string(line))
+    }
+
+    fn texts<'a>(layout: &TextLayout<'a>) -> Vec<&'a str> {
+        layout.lines().iter().map(|line| line.text).collect()
+    }
+
+    fn drawn(video: &MockBackend) -> Vec<String> {
+        video
+            .calls()
+            .iter()
+            .filter_map(|call| match call {
+                BackendCall::DrawString { text, .. } => Some(text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_overlong_words() {
+        let layout = layout(text_box(5.0, 4.0), "ABCDEFGHIJKL");
+
+        assert_eq!(texts(&layout), ["ABCDE", "FGHIJ", "KL"]);
+        assert_eq!(layout.width(), 30.0);
+        assert!(!layout.overflowed());
+    }
+
+    #[test]
+    fn overlong_word_starts_a_new_line() {
+        let layout = layout(text_box(5.0, 4.0), "HI ABCDEFGH");
+
+        assert_eq!(texts(&layout), ["HI", "ABCDE", "FGH"]);
+    }
+
+    #[test]
+    fn keeps_a_char_wider_than_the_box() {
+        let layout = layout(text_box(0.5, 4.0), "AB");
+
+        assert_eq!(texts(&layout), ["A", "B"]);
+    }
+
+    #[test]
+    fn empty_text_is_one_blank_line() {
+        let mut video = MockBackend::new(100.0, 100.0);
+        let layout = text_box(5.0, 2.0).draw("", &mut video);
+
+        assert_eq!(
+            layout.lines(),
+            &[Line {
+                text: "",
+                width: 0.0,
+                ellipsis: false,
+            }]
+        );
+        assert_eq!(layout.height(), TEXT_SIZE);
+        assert!(drawn(&video).is_empty());
+    }
+
+    #[test]
+    fn keeps_blank_paragraphs() {
+        let layout = layout(text_box(5.0, 4.0), "AB\n\nCD");
+
+        assert_eq!(texts(&layout), ["AB", "", "CD"]);
+    }
+
+    #[test]
+    fn fits_exact_width() {
+        assert_eq!(texts(&layout(text_box(5.0, 2.0), "ABCDE")), ["ABCDE"]);
+        assert_eq!(texts(&layout(text_box(5.0, 2.0), "AB CD")), ["AB CD"]);
+        assert_eq!(texts(&layout(text_box(5.0, 2.0), "AB CDE")), ["AB", "CDE"]);
+        assert_eq!(layout(text_box(5.0, 2.0), "ABCDE").width(), 30.0);
+    }
+
+    #[test]
+    fn fits_exact_height() {
+        let layout = layout(text_box(5.0, 2.0), "AB CD EF");
+
+        assert_eq!(texts(&layout), ["AB CD", "EF"]);
+        assert!(!layout.overflowed());
+    }
+
+    #[test]
+    fn clips_extra_lines() {
+        let layout = layout(text_box(5.0, 1.0), "AB CD EF");
+
+        assert_eq!(texts(&layout), ["AB CD"]);
+        assert!(layout.overflowed());
+        assert!(!layout.lines()[0].ellipsis);
+    }
+
+    #[test]
+    fn ellipsizes_last_line() {
+        let text_box = text_box(10.0, 2.0).with_overflow(Overflow::Ellipsis);
+        let mut video = MockBackend::new(100.0, 100.0);
+        let layout = text_box.draw("ONE TWO THREE FOUR FIVE SIX", &mut video);
+
+        assert_eq!(texts(&layout), ["ONE TWO", "THREE F"]);
+        assert!(layout.overflowed());
+        assert_eq!(
+            layout.lines()[1],
+            Line {
+                text: "THREE F",
+                width: 60.0,
+                ellipsis: true,
+            }
+        );
+        assert_eq!(drawn(&video), ["ONE TWO", "THREE F..."]);
+    }
+
+    #[test]
+    fn ellipsis_drops_trailing_spaces() {
+        let text_box = text_box(9.0, 1.0).with_overflow(Overflow::Ellipsis);
+        let layout = layout(text_box, "ABCDE FG HIJ");
+
+        assert_eq!(layout.lines()[0].text, "ABCDE");
+        assert_eq!(layout.lines()[0].width, 48.0);
+    }
+}
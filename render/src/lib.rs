@@ -0,0 +1,36 @@
+#![no_std]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+extern crate std;
+
+mod backend;
+mod bitmap_font;
+mod color;
+mod dirty;
+mod layout;
+mod metrics;
+#[cfg(any(test, feature = "mock"))]
+mod mock;
+mod overlay;
+mod primitives;
+mod queue;
+#[cfg(feature = "soft")]
+mod soft;
+mod transition;
+
+pub use backend::{PainterState, RenderBackend, PAINTER_STACK_SIZE};
+pub use bitmap_font::{BitmapFont, Glyph, GlyphAtlas, GREYBEARD};
+pub use color::{palette, tint_rgba, ColorExt, Gradient, Hsv};
+pub use dirty::DirtyRegions;
+pub use layout::{Line, Overflow, TextBox, TextLayout, VerticalAlign};
+pub use metrics::TextMetrics;
+#[cfg(any(test, feature = "mock"))]
+pub use mock::{BackendCall, MockBackend};
+pub use overlay::{Corner, DebugOverlay, FrameStats};
+pub use primitives::Primitives;
+pub use queue::{Layer, RenderMode, RenderQueue};
+#[cfg(feature = "soft")]
+pub use soft::SoftVideo;
+pub use transition::{Transition, TransitionKind, WipeDirection};
@@ -0,0 +1,87 @@
+use gpu::{Point, Rect, TextAlign};
+
+/// Part of the text size above the baseline. Text is positioned by the top of this box, the
+/// baseline sits `ascent` below it.
+const ASCENT: f64 = 1.0;
+/// Part of the text size below the baseline, taken by letters like "g" or "р".
+const DESCENT: f64 = 0.25;
+
+/// Extents of a single line of text. The GPU only measures width, vertical extents come from
+/// the text size. `BitmapFont::metrics` gives the extents of its own glyphs instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextMetrics {
+    pub width: f64,
+    pub ascent: f64,
+    pub descent: f64,
+}
+
+impl TextMetrics {
+    pub fn new(width: f64, size: f64) -> Self {
+        Self {
+            width,
+            ascent: size * ASCENT,
+            descent: size * DESCENT,
+        }
+    }
+
+    pub fn height(&self) -> f64 {
+        self.ascent + self.descent
+    }
+
+    /// Baseline for text whose top is at `position`.
+    pub fn baseline(&self, position: Point) -> Point {
+        Point::new(position.x, position.y + self.ascent)
+    }
+
+    /// Bounds of text drawn at `position`, whose `x` is the left edge, center or right edge
+    /// depending on `align`.
+    pub fn bounds(&self, position: Point, align: TextAlign) -> Rect {
+        let left = match align {
+            TextAlign::Left => position.x,
+            TextAlign::Center => position.x - self.width / 2.0,
+            TextAlign::Right => position.x - self.width,
+        };
+
+        Rect::new(
+            left,
+            position.y,
+            left + self.width,
+            position.y + self.height(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn baseline_is_a_text_size_below_the_top() {
+        let metrics = TextMetrics::new(40.0, 12.0);
+
+        assert_eq!(
+            metrics.baseline(Point::new(5.0, 10.0)),
+            Point::new(5.0, 22.0)
+        );
+        assert_eq!(metrics.height(), 15.0);
+    }
+
+    #[test]
+    fn bounds_follow_align() {
+        let metrics = TextMetrics::new(40.0, 12.0);
+        let position = Point::new(100.0, 10.0);
+
+        assert_eq!(
+            metrics.bounds(position, TextAlign::Left),
+            Rect::new(100.0, 10.0, 140.0, 25.0)
+        );
+        assert_eq!(
+            metrics.bounds(position, TextAlign::Center),
+            Rect::new(80.0, 10.0, 120.0, 25.0)
+        );
+        assert_eq!(
+            metrics.bounds(position, TextAlign::Right),
+            Rect::new(60.0, 10.0, 100.0, 25.0)
+        );
+    }
+}
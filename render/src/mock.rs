@@ -0,0 +1,415 @@
+use alloc::{string::String, vec::Vec};
+use gpu::{Color, Point, Rect, TextAlign};
+
+use crate::{RenderBackend, PAINTER_STACK_SIZE};
+
+/// Width of a character relative to the text size, used to fake measurements.
+const CHAR_WIDTH: f64 = 0.5;
+const DEFAULT_TEXT_SIZE: f64 = 12.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackendCall {
+    CreateTextObject {
+        object_id: u64,
+        text: String,
+    },
+    CreateImage {
+        object_id: u64,
+        width: u64,
+        height: u64,
+    },
+    DeleteObject {
+        object_id: u64,
+    },
+    PushState,
+    PopState,
+    SetPainterColor {
+        color: Color,
+    },
+    SetPainterTextSize {
+        size: f64,
+    },
+    SetPainterTextAlign {
+        align: TextAlign,
+    },
+    DrawRect {
+        rect: Rect,
+    },
+    DrawText {
+        object_id: u64,
+        position: Point,
+    },
+    DrawString {
+        text: String,
+        position: Point,
+    },
+    DrawImage {
+        object_id: u64,
+        position: Point,
+    },
+    DrawImageRect {
+        object_id: u64,
+        dst: Rect,
+    },
+    DrawImageRegion {
+        object_id: u64,
+        src: Rect,
+        dst: Rect,
+    },
+    FlipBuffers,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MockPainter {
+    color: Color,
+    text_size: f64,
+    text_align: TextAlign,
+}
+
+/// Backend that draws nothing and records every call, for checking what code sends to the GPU.
+#[derive(Debug, Clone)]
+pub struct MockBackend {
+    bounds: Rect,
+    calls: Vec<BackendCall>,
+    painter: MockPainter,
+    painter_stack: Vec<MockPainter>,
+    /// Pushes past the full stack, ignored like on the GPU.
+    painter_stack_overflow: usize,
+    texts: Vec<(u64, String)>,
+    next_object_id: u64,
+    generation: u64,
+}
+
+impl MockBackend {
+    pub fn new(width: f64, height: f64) -> Self {
+        Self {
+            bounds: Rect::new_from_zero(width, height),
+            calls: Vec::new(),
+            painter: MockPainter {
+                color: Color::white(),
+                text_size: DEFAULT_TEXT_SIZE,
+                text_align: TextAlign::Left,
+            },
+            painter_stack: Vec::new(),
+            painter_stack_overflow: 0,
+            texts: Vec::new(),
+            next_object_id: 1,
+            generation: 0,
+        }
+    }
+
+    pub fn calls(&self) -> &[BackendCall] {
+        &self.calls
+    }
+
+    pub fn take_calls(&mut self) -> Vec<BackendCall> {
+        core::mem::take(&mut self.calls)
+    }
+
+    pub fn count(&self, predicate: impl Fn(&BackendCall) -> bool) -> usize {
+        self.calls.iter().filter(|call| predicate(call)).count()
+    }
+
+    /// Loses every object like a reinitialized device, bumping the generation.
+    pub fn reinit(&mut self) {
+        self.texts.clear();
+        self.generation += 1;
+    }
+
+    fn next_object_id(&mut self) -> u64 {
+        let object_id = self.next_object_id;
+        self.next_object_id += 1;
+
+        object_id
+    }
+
+    fn text_width(&self, text: &str) -> f64 {
+        text.chars().count() as f64 * self.painter.text_size * CHAR_WIDTH
+    }
+}
+
+impl RenderBackend for MockBackend {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn create_text_object(&mut self, text: &str) -> Option<u64> {
+        let object_id = self.next_object_id();
+
+        self.texts.push((object_id, String::from(text)));
+        self.calls.push(BackendCall::CreateTextObject {
+            object_id,
+            text: String::from(text),
+        });
+
+        Some(object_id)
+    }
+
+    fn create_image(&mut self, _data: &[u8], width: u64, height: u64) -> Option<u64> {
+        let object_id = self.next_object_id();
+
+        self.calls.push(BackendCall::CreateImage {
+            object_id,
+            width,
+            height,
+        });
+
+        Some(object_id)
+    }
+
+    fn delete_object(&mut self, object_id: u64) {
+        self.texts.retain(|(id, _)| *id != object_id);
+        self.calls.push(BackendCall::DeleteObject { object_id });
+    }
+
+    fn push_state(&mut self) {
+        if self.painter_stack.len() >= PAINTER_STACK_SIZE {
+            self.painter_stack_overflow += 1;
+        } else {
+            self.painter_stack.push(self.painter);
+        }
+
+        self.calls.push(BackendCall::PushState);
+    }
+
+    fn pop_state(&mut self) {
+        if self.painter_stack_overflow > 0 {
+            self.painter_stack_overflow -= 1;
+        } else if let Some(painter) = self.painter_stack.pop() {
+            self.painter = painter;
+        }
+
+        self.calls.push(BackendCall::PopState);
+    }
+
+    fn set_painter_color(&mut self, color: Color) {
+        self.painter.color = color;
+        self.calls.push(BackendCall::SetPainterColor { color });
+    }
+
+    fn get_painter_color(&mut self) -> Color {
+        self.painter.color
+    }
+
+    fn set_painter_text_size(&mut self, size: f64) {
+        self.painter.text_size = size;
+        self.calls.push(BackendCall::SetPainterTextSize { size });
+    }
+
+    fn get_painter_text_size(&mut self) -> f64 {
+        self.painter.text_size
+    }
+
+    fn set_painter_text_align(&mut self, align: TextAlign) {
+        self.painter.text_align = align;
+        self.calls.push(BackendCall::SetPainterTextAlign { align });
+    }
+
+    fn get_painter_text_align(&mut self) -> TextAlign {
+        self.painter.text_align
+    }
+
+    fn draw_rect(&mut self, rect: Rect) {
+        self.calls.push(BackendCall::DrawRect { rect });
+    }
+
+    fn draw_text(&mut self, object_id: u64, position: Point) {
+        self.calls.push(BackendCall::DrawText {
+            object_id,
+            position,
+        });
+    }
+
+    fn draw_string(&mut self, text: &str, position: Point) {
+        self.calls.push(BackendCall::DrawString {
+            text: String::from(text),
+            position,
+        });
+    }
+
+    fn measure_string(&mut self, text: &str) -> f64 {
+        self.text_width(text)
+    }
+
+    fn mesaure_text(&mut self, object_id: u64) -> f64 {
+        match self.texts.iter().find(|(id, _)| *id == object_id) {
+            Some((_, text)) => self.text_width(text),
+            None => 0.0,
+        }
+    }
+
+    fn draw_image(&mut self, object_id: u64, position: Point) {
+        self.calls.push(BackendCall::DrawImage {
+            object_id,
+            position,
+        });
+    }
+
+    fn draw_image_rect(&mut self, object_id: u64, dst: Rect) {
+        self.calls
+            .push(BackendCall::DrawImageRect { object_id, dst });
+    }
+
+    fn draw_image_region(&mut self, object_id: u64, src: Rect, dst: Rect) {
+        self.calls.push(BackendCall::DrawImageRegion {
+            object_id,
+            src,
+            dst,
+        });
+    }
+
+    fn flip_buffers(&mut self) {
+        self.calls.push(BackendCall::FlipBuffers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn records_calls_in_order() {
+        let mut video = MockBackend::new(100.0, 50.0);
+        let object_id = video.create_text_object("HI").unwrap();
+
+        video.draw_text(object_id, Point::new(1.0, 2.0));
+        video.draw_rect(Rect::new(0.0, 0.0, 10.0, 10.0));
+        video.delete_object(object_id);
+        video.flip_buffers();
+
+        assert_eq!(
+            video.calls(),
+            &[
+                BackendCall::CreateTextObject {
+                    object_id,
+                    text: String::from("HI"),
+                },
+                BackendCall::DrawText {
+                    object_id,
+                    position: Point::new(1.0, 2.0),
+                },
+                BackendCall::DrawRect {
+                    rect: Rect::new(0.0, 0.0, 10.0, 10.0),
+                },
+                BackendCall::DeleteObject { object_id },
+                BackendCall::FlipBuffers,
+            ]
+        );
+    }
+
+    #[test]
+    fn gives_unique_object_ids() {
+        let mut video = MockBackend::new(100.0, 50.0);
+        let text = video.create_text_object("A").unwrap();
+        let image = video.create_image(&[0; 4], 1, 1).unwrap();
+
+        assert_ne!(text, image);
+        assert_eq!(
+            video.count(|call| matches!(
+                call,
+                BackendCall::CreateImage {
+                    width: 1,
+                    height: 1,
+                    ..
+                }
+            )),
+            1
+        );
+    }
+
+    #[test]
+    fn restores_painter_on_pop() {
+        let mut video = MockBackend::new(100.0, 50.0);
+
+        video.set_painter_color(Color::blue());
+        video.push_state();
+        video.set_painter_color(Color::white());
+        video.set_painter_text_size(24.0);
+        video.set_painter_text_align(TextAlign::Center);
+        video.pop_state();
+
+        assert_eq!(video.get_painter_color(), Color::blue());
+        assert_eq!(video.get_painter_text_size(), DEFAULT_TEXT_SIZE);
+        assert_eq!(video.get_painter_text_align(), TextAlign::Left);
+        assert_eq!(video.count(|call| *call == BackendCall::PushState), 1);
+        assert_eq!(video.count(|call| *call == BackendCall::PopState), 1);
+    }
+
+    #[test]
+    fn ignores_pushes_past_the_stack() {
+        let mut video = MockBackend::new(100.0, 50.0);
+
+        for size in 0..=PAINTER_STACK_SIZE {
+            video.set_painter_text_size(size as f64);
+            video.push_state();
+        }
+
+        video.set_painter_text_size(100.0);
+        video.pop_state();
+
+        assert_eq!(video.get_painter_text_size(), 100.0);
+
+        video.pop_state();
+
+        assert_eq!(
+            video.get_painter_text_size(),
+            (PAINTER_STACK_SIZE - 1) as f64
+        );
+    }
+
+    #[test]
+    fn measures_strings_and_text_objects() {
+        let mut video = MockBackend::new(100.0, 50.0);
+        let object_id = video.create_text_object("ABCD").unwrap();
+
+        assert_eq!(video.measure_string("ABCD"), 24.0);
+        assert_eq!(video.mesaure_text(object_id), 24.0);
+
+        video.set_painter_text_size(6.0);
+
+        assert_eq!(video.measure_string("ABCD"), 12.0);
+
+        video.delete_object(object_id);
+
+        assert_eq!(video.mesaure_text(object_id), 0.0);
+    }
+
+    #[test]
+    fn reinit_loses_objects() {
+        let mut video = MockBackend::new(100.0, 50.0);
+        let object_id = video.create_text_object("ABCD").unwrap();
+
+        video.reinit();
+
+        assert_eq!(video.generation(), 1);
+        assert_eq!(video.mesaure_text(object_id), 0.0);
+    }
+
+    #[test]
+    fn takes_calls() {
+        let mut video = MockBackend::new(100.0, 50.0);
+
+        video.fill_screen(Some(Color::black()));
+
+        assert_eq!(
+            video.take_calls(),
+            vec![
+                BackendCall::PushState,
+                BackendCall::SetPainterColor {
+                    color: Color::black(),
+                },
+                BackendCall::DrawRect {
+                    rect: Rect::new(0.0, 0.0, 100.0, 50.0),
+                },
+                BackendCall::PopState,
+            ]
+        );
+        assert!(video.calls().is_empty());
+    }
+}
@@ -0,0 +1,368 @@
+use alloc::string::String;
+use core::{fmt::Write, time::Duration};
+use gpu::{Color, Point, Rect, TextAlign};
+
+use crate::{RenderBackend, TextMetrics};
+
+/// Frame times kept for the averages, about a second at 60 FPS.
+const SAMPLES: usize = 60;
+const PADDING: f64 = 2.0;
+const DEFAULT_TEXT_SIZE: f64 = 8.0;
+/// Translucent, so the game stays visible under the panel.
+const DEFAULT_BACKGROUND: Color = Color {
+    a: 0xA0,
+    ..Color::black()
+};
+
+/// Numbers the overlay can't find out by itself, collected by the firmware each frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    pub gpu_ops: u64,
+    pub heap_used: usize,
+    pub heap_free: usize,
+    pub queued_events: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Debug panel with FPS, frame times and `FrameStats`. Call `frame` once per frame and `draw`
+/// right before flipping the buffers. The panel is translucent and fits the current text, so
+/// frames that only redraw what changed must redraw `bounds` before the next `draw`.
+#[derive(Debug, Clone)]
+pub struct DebugOverlay {
+    enabled: bool,
+    corner: Corner,
+    text_size: f64,
+    text_color: Color,
+    background: Color,
+    last_frame: Option<Duration>,
+    frame_times: [Duration; SAMPLES],
+    samples: usize,
+    next_sample: usize,
+    bounds: Option<Rect>,
+    text: String,
+}
+
+impl DebugOverlay {
+    pub const fn new() -> Self {
+        Self {
+            enabled: false,
+            corner: Corner::TopLeft,
+            text_size: DEFAULT_TEXT_SIZE,
+            text_color: Color::green(),
+            background: DEFAULT_BACKGROUND,
+            last_frame: None,
+            frame_times: [Duration::ZERO; SAMPLES],
+            samples: 0,
+            next_sample: 0,
+            bounds: None,
+            text: String::new(),
+        }
+    }
+
+    pub fn with_corner(mut self, corner: Corner) -> Self {
+        self.corner = corner;
+
+        self
+    }
+
+    pub fn with_text_size(mut self, size: f64) -> Self {
+        self.text_size = size;
+
+        self
+    }
+
+    pub fn with_colors(mut self, text: Color, background: Color) -> Self {
+        self.text_color = text;
+        self.background = background;
+
+        self
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.bounds = None;
+    }
+
+    /// Whatever was under the panel must be redrawn after hiding it.
+    pub fn toggle(&mut self) {
+        self.set_enabled(!self.enabled);
+    }
+
+    /// Panel drawn by the last `draw`, `None` while disabled.
+    pub fn bounds(&self) -> Option<Rect> {
+        self.bounds
+    }
+
+    pub fn frame(&mut self, now: Duration) {
+        if let Some(last_frame) = self.last_frame {
+            self.frame_times[self.next_sample] = now.saturating_sub(last_frame);
+            self.next_sample = (self.next_sample + 1) % SAMPLES;
+            self.samples = usize::min(self.samples + 1, SAMPLES);
+        }
+
+        self.last_frame = Some(now);
+    }
+
+    fn frame_times(&self) -> &[Duration] {
+        &self.frame_times[..self.samples]
+    }
+
+    pub fn average_frame_time(&self) -> Duration {
+        match self.samples {
+            0 => Duration::ZERO,
+            samples => self.frame_times().iter().sum::<Duration>() / samples as u32,
+        }
+    }
+
+    pub fn max_frame_time(&self) -> Duration {
+        self.frame_times()
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or(Duration::ZERO)
+    }
+
+    pub fn fps(&self) -> f64 {
+        match self.average_frame_time().as_secs_f64() {
+            seconds if seconds > 0.0 => 1.0 / seconds,
+            _ => 0.0,
+        }
+    }
+
+    fn format(&mut self, stats: &FrameStats) -> core::fmt::Result {
+        let fps = self.fps();
+        let average = self.average_frame_time().as_secs_f64() * 1000.0;
+        let max = self.max_frame_time().as_secs_f64() * 1000.0;
+
+        self.text.clear();
+        writeln!(self.text, "FPS: {fps:.1}")?;
+        writeln!(self.text, "FRAME: {average:.1}/{max:.1} MS")?;
+        writeln!(self.text, "GPU OPS: {}", stats.gpu_ops)?;
+        writeln!(
+            self.text,
+            "HEAP: {}/{} KB",
+            stats.heap_used / 1024,
+            (stats.heap_used + stats.heap_free) / 1024
+        )?;
+        write!(self.text, "EVENTS: {}", stats.queued_events)
+    }
+
+    pub fn draw(&mut self, video: &mut impl RenderBackend, stats: &FrameStats) {
+        if !self.enabled || self.format(stats).is_err() {
+            return;
+        }
+
+        video.push_state();
+        video.set_painter_text_size(self.text_size);
+        video.set_painter_text_align(TextAlign::Left);
+
+        let text_width = self
+            .text
+            .lines()
+            .map(|line| video.measure_string(line))
+            .fold(0.0, f64::max);
+        let metrics = TextMetrics::new(text_width, self.text_size);
+        let lines = self.text.lines().count() as f64;
+        let width = text_width + PADDING * 2.0;
+        let height = metrics.height() * lines + PADDING * 2.0;
+        let bounds = video.bounds();
+        let position = Point::new(
+            match self.corner {
+                Corner::TopLeft | Corner::BottomLeft => 0.0,
+                Corner::TopRight | Corner::BottomRight => bounds.width() - width,
+            },
+            match self.corner {
+                Corner::TopLeft | Corner::TopRight => 0.0,
+                Corner::BottomLeft | Corner::BottomRight => bounds.height() - height,
+            },
+        );
+
+        let panel = Rect::new_from_position(position, width, height);
+
+        self.bounds = Some(panel);
+        video.set_painter_color(self.background);
+        video.draw_rect(panel);
+        video.set_painter_color(self.text_color);
+
+        let mut top = Point::new(position.x + PADDING, position.y + PADDING);
+
+        for line in self.text.lines() {
+            video.draw_string(line, metrics.baseline(top));
+            top.y += metrics.height();
+        }
+
+        video.pop_state();
+    }
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec::Vec};
+
+    use super::*;
+    use crate::{BackendCall, MockBackend};
+
+    /// Feeds frames `interval_ms` apart, the first one only starts the count.
+    fn run(overlay: &mut DebugOverlay, intervals_ms: &[u64]) {
+        let mut now = Duration::from_millis(1000);
+
+        overlay.frame(now);
+
+        for interval in intervals_ms {
+            now += Duration::from_millis(*interval);
+            overlay.frame(now);
+        }
+    }
+
+    fn enabled(corner: Corner) -> DebugOverlay {
+        let mut overlay = DebugOverlay::new().with_corner(corner);
+
+        overlay.set_enabled(true);
+
+        overlay
+    }
+
+    fn strings(video: &MockBackend) -> Vec<(String, Point)> {
+        video
+            .calls()
+            .iter()
+            .filter_map(|call| match call {
+                BackendCall::DrawString { text, position } => Some((text.clone(), *position)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn no_frames_is_zero_fps() {
+        let mut overlay = DebugOverlay::new();
+
+        overlay.frame(Duration::from_millis(16));
+
+        assert_eq!(overlay.fps(), 0.0);
+        assert_eq!(overlay.average_frame_time(), Duration::ZERO);
+        assert_eq!(overlay.max_frame_time(), Duration::ZERO);
+    }
+
+    #[test]
+    fn averages_frame_times() {
+        let mut overlay = DebugOverlay::new();
+
+        run(&mut overlay, &[10, 20, 30]);
+
+        assert_eq!(overlay.average_frame_time(), Duration::from_millis(20));
+        assert_eq!(overlay.max_frame_time(), Duration::from_millis(30));
+        assert!((overlay.fps() - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn forgets_frames_past_the_samples() {
+        let mut overlay = DebugOverlay::new();
+        let mut intervals = alloc::vec![100];
+
+        intervals.extend([20; SAMPLES]);
+        run(&mut overlay, &intervals);
+
+        assert_eq!(overlay.average_frame_time(), Duration::from_millis(20));
+        assert_eq!(overlay.max_frame_time(), Duration::from_millis(20));
+        assert!((overlay.fps() - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn draws_nothing_while_disabled() {
+        let mut video = MockBackend::new(200.0, 100.0);
+        let mut overlay = DebugOverlay::new();
+
+        overlay.draw(&mut video, &FrameStats::default());
+
+        assert!(video.calls().is_empty());
+        assert_eq!(overlay.bounds(), None);
+    }
+
+    #[test]
+    fn draws_panel_then_lines() {
+        let mut video = MockBackend::new(200.0, 100.0);
+        let mut overlay = enabled(Corner::TopLeft);
+
+        run(&mut overlay, &[10, 30]);
+        overlay.draw(&mut video, &FrameStats::default());
+
+        // The mock measures 4 pixels per character at size 8, lines are 10 pixels high.
+        let panel = Rect::new(0.0, 0.0, 19.0 * 4.0 + 4.0, 54.0);
+        let rect = video
+            .calls()
+            .iter()
+            .position(|call| *call == BackendCall::DrawRect { rect: panel })
+            .unwrap();
+
+        assert_eq!(overlay.bounds(), Some(panel));
+        assert!(matches!(
+            video.calls()[rect - 1],
+            BackendCall::SetPainterColor { color } if color.a < u8::MAX
+        ));
+        assert_eq!(
+            strings(&video),
+            [
+                ("FPS: 50.0", 10.0),
+                ("FRAME: 20.0/30.0 MS", 20.0),
+                ("GPU OPS: 0", 30.0),
+                ("HEAP: 0/0 KB", 40.0),
+                ("EVENTS: 0", 50.0),
+            ]
+            .map(|(text, y)| (text.to_string(), Point::new(2.0, y)))
+        );
+        assert_eq!(video.calls().first(), Some(&BackendCall::PushState));
+        assert_eq!(video.calls().last(), Some(&BackendCall::PopState));
+    }
+
+    #[test]
+    fn places_panel_in_corner() {
+        let mut video = MockBackend::new(200.0, 100.0);
+        let mut overlay = enabled(Corner::BottomRight);
+
+        overlay.draw(&mut video, &FrameStats::default());
+
+        let panel = overlay.bounds().unwrap();
+
+        assert_eq!(panel.position(), Point::new(200.0 - panel.width(), 46.0));
+        assert_eq!(
+            strings(&video)[0].1,
+            Point::new(202.0 - panel.width(), 56.0)
+        );
+    }
+
+    #[test]
+    fn panel_shrinks_with_the_text() {
+        let mut video = MockBackend::new(400.0, 100.0);
+        let mut overlay = enabled(Corner::TopLeft);
+        let busy = FrameStats {
+            gpu_ops: 1_000_000_000_000,
+            ..FrameStats::default()
+        };
+
+        overlay.draw(&mut video, &busy);
+
+        let wide = overlay.bounds().unwrap();
+
+        overlay.draw(&mut video, &FrameStats::default());
+
+        assert!(overlay.bounds().unwrap().width() < wide.width());
+    }
+}
@@ -0,0 +1,477 @@
+use alloc::{vec, vec::Vec};
+use gpu::{Color, Point, Rect};
+
+use crate::RenderBackend;
+
+/// Collects plotted pixels into straight runs, so a line costs one op per run, not per pixel.
+struct Span {
+    start: (i64, i64),
+    length: i64,
+    horizontal: bool,
+}
+
+impl Span {
+    fn new(x: i64, y: i64, horizontal: bool) -> Self {
+        Self {
+            start: (x, y),
+            length: 1,
+            horizontal,
+        }
+    }
+
+    fn extends(&self, x: i64, y: i64) -> bool {
+        let (start_x, start_y) = self.start;
+
+        if self.horizontal {
+            y == start_y && (x == start_x + self.length || x == start_x - 1)
+        } else {
+            x == start_x && (y == start_y + self.length || y == start_y - 1)
+        }
+    }
+
+    fn extend(&mut self, x: i64, y: i64) {
+        if x < self.start.0 || y < self.start.1 {
+            self.start = (x, y);
+        }
+
+        self.length += 1;
+    }
+
+    fn rect(&self) -> Rect {
+        let (x, y) = (self.start.0 as f64, self.start.1 as f64);
+        let length = self.length as f64;
+
+        if self.horizontal {
+            Rect::new(x, y, x + length, y + 1.0)
+        } else {
+            Rect::new(x, y, x + 1.0, y + length)
+        }
+    }
+}
+
+/// Shapes built from `draw_rect`, available on every backend.
+pub trait Primitives: RenderBackend {
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        self.push_state();
+        self.set_painter_color(color);
+        self.draw_rect(rect);
+        self.pop_state();
+    }
+
+    fn stroke_rect(&mut self, rect: Rect, color: Color, thickness: f64) {
+        let Point { x, y } = rect.position();
+        let (width, height) = (rect.width(), rect.height());
+        let thickness = f64::min(thickness, f64::min(width, height) / 2.0);
+
+        self.push_state();
+        self.set_painter_color(color);
+        self.draw_rect(Rect::new(x, y, x + width, y + thickness));
+        self.draw_rect(Rect::new(x, y + height - thickness, x + width, y + height));
+        self.draw_rect(Rect::new(
+            x,
+            y + thickness,
+            x + thickness,
+            y + height - thickness,
+        ));
+        self.draw_rect(Rect::new(
+            x + width - thickness,
+            y + thickness,
+            x + width,
+            y + height - thickness,
+        ));
+        self.pop_state();
+    }
+
+    fn draw_hline(&mut self, from: Point, length: f64, color: Color) {
+        self.fill_rect(
+            Rect::new(from.x, from.y, from.x + length, from.y + 1.0),
+            color,
+        );
+    }
+
+    fn draw_vline(&mut self, from: Point, length: f64, color: Color) {
+        self.fill_rect(
+            Rect::new(from.x, from.y, from.x + 1.0, from.y + length),
+            color,
+        );
+    }
+
+    /// Bresenham line including both ends.
+    fn draw_line(&mut self, from: Point, to: Point, color: Color) {
+        let (mut x, mut y) = (from.x as i64, from.y as i64);
+        let (to_x, to_y) = (to.x as i64, to.y as i64);
+        let dx = (to_x - x).abs();
+        let dy = -(to_y - y).abs();
+        let step_x = if x < to_x { 1 } else { -1 };
+        let step_y = if y < to_y { 1 } else { -1 };
+        let horizontal = dx >= -dy;
+        let mut error = dx + dy;
+        let mut span = Span::new(x, y, horizontal);
+
+        self.push_state();
+        self.set_painter_color(color);
+
+        while x != to_x || y != to_y {
+            let error2 = error * 2;
+
+            if error2 >= dy {
+                error += dy;
+                x += step_x;
+            }
+
+            if error2 <= dx {
+                error += dx;
+                y += step_y;
+            }
+
+            if span.extends(x, y) {
+                span.extend(x, y);
+            } else {
+                self.draw_rect(span.rect());
+                span = Span::new(x, y, horizontal);
+            }
+        }
+
+        self.draw_rect(span.rect());
+        self.pop_state();
+    }
+
+    /// Draws every row once at its widest, so translucent colors blend evenly.
+    fn fill_circle(&mut self, center: Point, radius: f64, color: Color) {
+        let (center_x, center_y) = (center.x as i64, center.y as i64);
+        let mut x = radius as i64;
+        let mut y = 0;
+        let mut error = 1 - x;
+
+        if x < 0 {
+            return;
+        }
+
+        // Half widths of the rows by their distance from the center.
+        let mut halves = vec![0; x as usize + 1];
+
+        while x >= y {
+            halves[y as usize] = i64::max(halves[y as usize], x);
+            halves[x as usize] = i64::max(halves[x as usize], y);
+
+            y += 1;
+
+            if error < 0 {
+                error += 2 * y + 1;
+            } else {
+                x -= 1;
+                error += 2 * (y - x) + 1;
+            }
+        }
+
+        let last = halves.len() as i64 - 1;
+
+        self.push_state();
+        self.set_painter_color(color);
+
+        for row in -last..=last {
+            let half = halves[row.unsigned_abs() as usize];
+
+            self.draw_rect(Rect::new(
+                (center_x - half) as f64,
+                (center_y + row) as f64,
+                (center_x + half + 1) as f64,
+                (center_y + row + 1) as f64,
+            ));
+        }
+
+        self.pop_state();
+    }
+
+    /// Draws every pixel of the outline once.
+    fn stroke_circle(&mut self, center: Point, radius: f64, color: Color) {
+        let (center_x, center_y) = (center.x as i64, center.y as i64);
+        let mut x = radius as i64;
+        let mut y = 0;
+        let mut error = 1 - x;
+        let mut pixels = Vec::new();
+
+        while x >= y {
+            for (dx, dy) in [
+                (x, y),
+                (-x, y),
+                (x, -y),
+                (-x, -y),
+                (y, x),
+                (-y, x),
+                (y, -x),
+                (-y, -x),
+            ] {
+                pixels.push((center_y + dy, center_x + dx));
+            }
+
+            y += 1;
+
+            if error < 0 {
+                error += 2 * y + 1;
+            } else {
+                x -= 1;
+                error += 2 * (y - x) + 1;
+            }
+        }
+
+        // The octants meet on the axes and diagonals.
+        pixels.sort_unstable();
+        pixels.dedup();
+
+        self.push_state();
+        self.set_painter_color(color);
+
+        for (py, px) in pixels {
+            let (px, py) = (px as f64, py as f64);
+
+            self.draw_rect(Rect::new(px, py, px + 1.0, py + 1.0));
+        }
+
+        self.pop_state();
+    }
+
+    /// Draws `rect` filled by `progress` (0.0..=1.0) from the left, with an optional border.
+    fn draw_progress_bar(
+        &mut self,
+        rect: Rect,
+        progress: f64,
+        fill: Color,
+        background: Color,
+        border: Option<Color>,
+    ) {
+        let progress = progress.clamp(0.0, 1.0);
+        let Point { x, y } = rect.position();
+        let filled = rect.width() * progress;
+
+        self.push_state();
+        self.set_painter_color(background);
+        self.draw_rect(rect);
+
+        if filled > 0.0 {
+            self.set_painter_color(fill);
+            self.draw_rect(Rect::new(x, y, x + filled, y + rect.height()));
+        }
+
+        if let Some(border) = border {
+            self.stroke_rect(rect, border, 1.0);
+        }
+
+        self.pop_state();
+    }
+}
+
+impl<B> Primitives for B where B: RenderBackend + ?Sized {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BackendCall, MockBackend};
+
+    fn rects(video: &MockBackend) -> Vec<Rect> {
+        video
+            .calls()
+            .iter()
+            .filter_map(|call| match call {
+                BackendCall::DrawRect { rect } => Some(*rect),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn line(from: (f64, f64), to: (f64, f64)) -> Vec<Rect> {
+        let mut video = MockBackend::new(100.0, 100.0);
+
+        video.draw_line(
+            Point::new(from.0, from.1),
+            Point::new(to.0, to.1),
+            Color::white(),
+        );
+
+        rects(&video)
+    }
+
+    #[test]
+    fn straight_lines_are_one_rect() {
+        assert_eq!(
+            line((0.0, 0.0), (4.0, 0.0)),
+            [Rect::new(0.0, 0.0, 5.0, 1.0)]
+        );
+        assert_eq!(
+            line((4.0, 0.0), (0.0, 0.0)),
+            [Rect::new(0.0, 0.0, 5.0, 1.0)]
+        );
+        assert_eq!(
+            line((2.0, 1.0), (2.0, 4.0)),
+            [Rect::new(2.0, 1.0, 3.0, 5.0)]
+        );
+        assert_eq!(
+            line((3.0, 3.0), (3.0, 3.0)),
+            [Rect::new(3.0, 3.0, 4.0, 4.0)]
+        );
+    }
+
+    #[test]
+    fn shallow_line_merges_horizontal_runs() {
+        let runs = [
+            Rect::new(0.0, 0.0, 2.0, 1.0),
+            Rect::new(2.0, 1.0, 4.0, 2.0),
+            Rect::new(4.0, 2.0, 6.0, 3.0),
+        ];
+
+        assert_eq!(line((0.0, 0.0), (5.0, 2.0)), runs);
+    }
+
+    #[test]
+    fn steep_line_merges_vertical_runs() {
+        let runs = [
+            Rect::new(0.0, 0.0, 1.0, 2.0),
+            Rect::new(1.0, 2.0, 2.0, 4.0),
+            Rect::new(2.0, 4.0, 3.0, 6.0),
+        ];
+
+        assert_eq!(line((0.0, 0.0), (2.0, 5.0)), runs);
+    }
+
+    #[test]
+    fn reversed_lines_cover_the_same_pixels() {
+        for (from, to) in [((0.0, 0.0), (5.0, 2.0)), ((0.0, 0.0), (2.0, 5.0))] {
+            let mut reversed = line(to, from);
+
+            reversed.reverse();
+
+            assert_eq!(reversed, line(from, to));
+        }
+    }
+
+    #[test]
+    fn diagonal_line_is_one_rect_per_pixel() {
+        assert_eq!(line((0.0, 0.0), (3.0, 3.0)).len(), 4);
+    }
+
+    #[test]
+    fn stroke_rect_draws_sides_between_edges() {
+        let mut video = MockBackend::new(100.0, 100.0);
+
+        video.stroke_rect(Rect::new(0.0, 0.0, 10.0, 6.0), Color::white(), 2.0);
+
+        assert_eq!(
+            rects(&video),
+            [
+                Rect::new(0.0, 0.0, 10.0, 2.0),
+                Rect::new(0.0, 4.0, 10.0, 6.0),
+                Rect::new(0.0, 2.0, 2.0, 4.0),
+                Rect::new(8.0, 2.0, 10.0, 4.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn stroke_rect_clamps_thickness() {
+        let mut video = MockBackend::new(100.0, 100.0);
+
+        video.stroke_rect(Rect::new(0.0, 0.0, 10.0, 6.0), Color::white(), 5.0);
+
+        assert_eq!(rects(&video)[0], Rect::new(0.0, 0.0, 10.0, 3.0));
+        assert_eq!(rects(&video)[1], Rect::new(0.0, 3.0, 10.0, 6.0));
+    }
+
+    #[test]
+    fn fill_circle_draws_every_row_once() {
+        let mut video = MockBackend::new(100.0, 100.0);
+
+        video.fill_circle(Point::new(10.0, 10.0), 2.0, Color::white());
+
+        assert_eq!(
+            rects(&video),
+            [
+                Rect::new(9.0, 8.0, 12.0, 9.0),
+                Rect::new(8.0, 9.0, 13.0, 10.0),
+                Rect::new(8.0, 10.0, 13.0, 11.0),
+                Rect::new(8.0, 11.0, 13.0, 12.0),
+                Rect::new(9.0, 12.0, 12.0, 13.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn fill_circle_rows_are_unique() {
+        let mut video = MockBackend::new(100.0, 100.0);
+
+        video.fill_circle(Point::new(50.0, 50.0), 20.0, Color::white());
+
+        let rows: Vec<f64> = rects(&video).iter().map(|rect| rect.position().y).collect();
+
+        assert_eq!(rows.len(), 41);
+        assert!(rows.windows(2).all(|pair| pair[1] == pair[0] + 1.0));
+    }
+
+    #[test]
+    fn circle_of_zero_radius_is_a_pixel() {
+        let mut video = MockBackend::new(100.0, 100.0);
+
+        video.fill_circle(Point::new(5.0, 5.0), 0.0, Color::white());
+        video.stroke_circle(Point::new(5.0, 5.0), 0.0, Color::white());
+        video.fill_circle(Point::new(5.0, 5.0), -1.0, Color::white());
+
+        assert_eq!(rects(&video), [Rect::new(5.0, 5.0, 6.0, 6.0); 2]);
+    }
+
+    #[test]
+    fn stroke_circle_draws_every_pixel_once() {
+        let mut video = MockBackend::new(100.0, 100.0);
+
+        video.stroke_circle(Point::new(10.0, 10.0), 2.0, Color::white());
+
+        let pixels = rects(&video);
+        let mut unique = pixels.clone();
+
+        unique.dedup();
+
+        assert_eq!(pixels, unique);
+        assert_eq!(pixels.len(), 12);
+        assert!(pixels.contains(&Rect::new(12.0, 10.0, 13.0, 11.0)));
+        assert!(pixels.contains(&Rect::new(10.0, 8.0, 11.0, 9.0)));
+    }
+
+    #[test]
+    fn progress_bar_clamps_progress() {
+        let rect = Rect::new(0.0, 0.0, 20.0, 4.0);
+        let draw = |progress| {
+            let mut video = MockBackend::new(100.0, 100.0);
+
+            video.draw_progress_bar(rect, progress, Color::white(), Color::black(), None);
+            rects(&video)
+        };
+
+        assert_eq!(draw(-0.5), [rect]);
+        assert_eq!(draw(0.25), [rect, Rect::new(0.0, 0.0, 5.0, 4.0)]);
+        assert_eq!(draw(1.5), [rect, rect]);
+    }
+
+    #[test]
+    fn progress_bar_border_is_drawn_last() {
+        let mut video = MockBackend::new(100.0, 100.0);
+        let rect = Rect::new(0.0, 0.0, 20.0, 4.0);
+
+        video.draw_progress_bar(
+            rect,
+            0.5,
+            Color::white(),
+            Color::black(),
+            Some(Color::blue()),
+        );
+
+        assert_eq!(rects(&video).len(), 6);
+        assert_eq!(
+            video
+                .calls()
+                .iter()
+                .rev()
+                .find(|call| matches!(call, BackendCall::SetPainterColor { .. })),
+            Some(&BackendCall::SetPainterColor {
+                color: Color::blue(),
+            })
+        );
+    }
+}
@@ -0,0 +1,618 @@
+use alloc::{string::String, vec::Vec};
+use gpu::{Color, Point, Rect, TextAlign};
+
+use crate::{DirtyRegions, PainterState, RenderBackend, TextMetrics};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    Background,
+    Sprites,
+    Hud,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Every queued command is drawn each frame.
+    Full,
+    /// Only commands that changed since the previous frame, and whatever they overlap, are drawn.
+    Dirty,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DrawCommand {
+    Rect(Rect),
+    Image {
+        object_id: u64,
+        bounds: Rect,
+    },
+    ImageRect {
+        object_id: u64,
+        dst: Rect,
+    },
+    ImageRegion {
+        object_id: u64,
+        src: Rect,
+        dst: Rect,
+    },
+    Text {
+        object_id: u64,
+        position: Point,
+    },
+    String {
+        text: String,
+        position: Point,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct QueuedCommand {
+    layer: Layer,
+    state: PainterState,
+    bounds: Option<Rect>,
+    command: DrawCommand,
+}
+
+impl QueuedCommand {
+    fn same_as(&self, other: &QueuedCommand) -> bool {
+        self.layer == other.layer && self.state == other.state && self.command == other.command
+    }
+}
+
+/// Draw commands recorded during a frame. Commands are submitted layer by layer, in the order
+/// they were queued inside a layer, and the painter state is only set when it differs from the
+/// one of the previous command.
+#[derive(Debug, Clone)]
+pub struct RenderQueue {
+    commands: Vec<QueuedCommand>,
+    mode: RenderMode,
+    previous: Vec<QueuedCommand>,
+    previous_dirty: DirtyRegions,
+    damaged: DirtyRegions,
+    full_redraw: bool,
+}
+
+impl RenderQueue {
+    pub const fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+            mode: RenderMode::Full,
+            previous: Vec::new(),
+            previous_dirty: DirtyRegions::new(),
+            damaged: DirtyRegions::new(),
+            full_redraw: true,
+        }
+    }
+
+    pub fn mode(&self) -> RenderMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: RenderMode) {
+        if self.mode != mode {
+            self.mode = mode;
+            self.invalidate();
+        }
+    }
+
+    /// Forces the next frames to be drawn completely, e.g. after something was drawn bypassing
+    /// the queue.
+    pub fn invalidate(&mut self) {
+        self.full_redraw = true;
+        self.previous.clear();
+        self.previous_dirty.clear();
+    }
+
+    /// Redraws whatever is under `rect` on the next submit, e.g. where something was drawn over
+    /// the queued commands.
+    pub fn damage(&mut self, rect: Rect) {
+        self.damaged.add(rect);
+    }
+
+    fn push(&mut self, layer: Layer, state: PainterState, command: DrawCommand) {
+        self.commands.push(QueuedCommand {
+            layer,
+            state,
+            bounds: None,
+            command,
+        });
+    }
+
+    pub fn rect(&mut self, layer: Layer, rect: Rect, color: Color) {
+        let state = PainterState {
+            color: Some(color),
+            ..PainterState::default()
+        };
+
+        self.push(layer, state, DrawCommand::Rect(rect));
+    }
+
+    /// Queues an image drawn at the position of `bounds`, the size is used to track what it
+    /// covers.
+    pub fn image(&mut self, layer: Layer, object_id: u64, bounds: Rect) {
+        self.push(
+            layer,
+            PainterState::default(),
+            DrawCommand::Image { object_id, bounds },
+        );
+    }
+
+    pub fn image_rect(&mut self, layer: Layer, object_id: u64, dst: Rect) {
+        self.push(
+            layer,
+            PainterState::default(),
+            DrawCommand::ImageRect { object_id, dst },
+        );
+    }
+
+    /// Queues the `src` part of an image, e.g. an atlas sprite.
+    pub fn image_region(&mut self, layer: Layer, object_id: u64, src: Rect, dst: Rect) {
+        self.push(
+            layer,
+            PainterState::default(),
+            DrawCommand::ImageRegion {
+                object_id,
+                src,
+                dst,
+            },
+        );
+    }
+
+    /// Queues a text object, `position` is the top-left corner as in `Text::draw`.
+    pub fn text(&mut self, layer: Layer, object_id: u64, position: Point, state: PainterState) {
+        self.push(
+            layer,
+            state,
+            DrawCommand::Text {
+                object_id,
+                position,
+            },
+        );
+    }
+
+    /// Queues a copy of `text`, `position` is the top-left corner as in `Text::draw`.
+    pub fn string(&mut self, layer: Layer, text: &str, position: Point, state: PainterState) {
+        self.push(
+            layer,
+            state,
+            DrawCommand::String {
+                text: String::from(text),
+                position,
+            },
+        );
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    /// Stable, so commands of a layer keep their order and overlapping ones draw as queued.
+    fn sort(&mut self) {
+        self.commands.sort_by_key(|command| command.layer);
+    }
+
+    fn apply_state(state: &PainterState, video: &mut impl RenderBackend) {
+        if let Some(color) = state.color {
+            video.set_painter_color(color);
+        }
+
+        if let Some(size) = state.text_size {
+            video.set_painter_text_size(size);
+        }
+
+        if let Some(align) = state.text_align {
+            video.set_painter_text_align(align);
+        }
+    }
+
+    fn text_bounds(
+        state: &PainterState,
+        position: Point,
+        width: f64,
+        video: &mut impl RenderBackend,
+    ) -> Rect {
+        let size = state
+            .text_size
+            .unwrap_or_else(|| video.get_painter_text_size());
+
+        TextMetrics::new(width, size).bounds(position, state.text_align.unwrap_or(TextAlign::Left))
+    }
+
+    fn calc_bounds(command: &QueuedCommand, video: &mut impl RenderBackend) -> Rect {
+        match command.command {
+            DrawCommand::Rect(rect) => rect,
+            DrawCommand::Image { bounds, .. } => bounds,
+            DrawCommand::ImageRect { dst, .. } | DrawCommand::ImageRegion { dst, .. } => dst,
+            DrawCommand::Text {
+                object_id,
+                position,
+            } => {
+                video.push_state();
+                Self::apply_state(&command.state, video);
+                let width = video.mesaure_text(object_id);
+                video.pop_state();
+
+                Self::text_bounds(&command.state, position, width, video)
+            }
+            DrawCommand::String { ref text, position } => {
+                video.push_state();
+                Self::apply_state(&command.state, video);
+                let width = video.measure_string(text);
+                video.pop_state();
+
+                Self::text_bounds(&command.state, position, width, video)
+            }
+        }
+    }
+
+    /// Sets the state of `command` unless it is the `current` one already.
+    fn switch_state(
+        command: &QueuedCommand,
+        current: &mut Option<PainterState>,
+        video: &mut impl RenderBackend,
+    ) {
+        if *current != Some(command.state) {
+            Self::apply_state(&command.state, video);
+            *current = Some(command.state);
+        }
+    }
+
+    fn draw(
+        command: &QueuedCommand,
+        current: &mut Option<PainterState>,
+        video: &mut impl RenderBackend,
+    ) {
+        Self::switch_state(command, current, video);
+
+        match command.command {
+            DrawCommand::Rect(rect) => video.draw_rect(rect),
+            DrawCommand::Image { object_id, bounds } => {
+                video.draw_image(object_id, bounds.position())
+            }
+            DrawCommand::ImageRect { object_id, dst } => video.draw_image_rect(object_id, dst),
+            DrawCommand::ImageRegion {
+                object_id,
+                src,
+                dst,
+            } => video.draw_image_region(object_id, src, dst),
+            DrawCommand::Text {
+                object_id,
+                position,
+            } => {
+                let metrics = TextMetrics::new(0.0, video.get_painter_text_size());
+
+                video.draw_text(object_id, metrics.baseline(position))
+            }
+            DrawCommand::String { ref text, position } => {
+                let metrics = TextMetrics::new(0.0, video.get_painter_text_size());
+
+                video.draw_string(text, metrics.baseline(position))
+            }
+        }
+    }
+
+    fn collect_dirty(&mut self, video: &mut impl RenderBackend) -> DirtyRegions {
+        let mut dirty = DirtyRegions::new();
+        let mut matched = alloc::vec![false; self.previous.len()];
+
+        for command in &mut self.commands {
+            let previous = self
+                .previous
+                .iter()
+                .enumerate()
+                .position(|(index, previous)| !matched[index] && previous.same_as(command));
+
+            match previous {
+                Some(index) => {
+                    matched[index] = true;
+                    command.bounds = self.previous[index].bounds;
+                }
+                None => {
+                    let bounds = Self::calc_bounds(command, video);
+
+                    command.bounds = Some(bounds);
+                    dirty.add(bounds);
+                }
+            }
+        }
+
+        for (index, previous) in self.previous.iter().enumerate() {
+            if let (false, Some(bounds)) = (matched[index], previous.bounds) {
+                dirty.add(bounds);
+            }
+        }
+
+        dirty.extend(&self.damaged);
+
+        // Both buffers must receive the changes, so regions of the last frame are redrawn again.
+        let mut regions = dirty.clone();
+        regions.extend(&self.previous_dirty);
+        self.previous_dirty = dirty;
+
+        // Commands that can't be clipped are redrawn whole, everything they cover is redrawn too.
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for command in &self.commands {
+                let Some(bounds) = command.bounds else {
+                    continue;
+                };
+
+                if !matches!(command.command, DrawCommand::Rect(_)) && regions.intersects(&bounds) {
+                    changed |= regions.add(bounds);
+                }
+            }
+        }
+
+        regions
+    }
+
+    fn submit_dirty(&mut self, video: &mut impl RenderBackend) {
+        let regions = self.collect_dirty(video);
+        let mut current = None;
+
+        for command in &self.commands {
+            let Some(bounds) = command.bounds else {
+                continue;
+            };
+
+            if let DrawCommand::Rect(rect) = command.command {
+                for clipped in regions.clip(&rect) {
+                    Self::switch_state(command, &mut current, video);
+                    video.draw_rect(clipped);
+                }
+            } else if regions.intersects(&bounds) {
+                Self::draw(command, &mut current, video);
+            }
+        }
+    }
+
+    /// Draws the queued commands and empties the queue, call before `flip_buffers`.
+    pub fn submit(&mut self, video: &mut impl RenderBackend) {
+        let mut current = None;
+
+        self.sort();
+
+        match self.mode {
+            RenderMode::Full => {
+                for command in &self.commands {
+                    Self::draw(command, &mut current, video);
+                }
+
+                self.commands.clear();
+            }
+            RenderMode::Dirty if self.full_redraw => {
+                for command in &mut self.commands {
+                    command.bounds = Some(Self::calc_bounds(command, video));
+                    Self::draw(command, &mut current, video);
+                }
+
+                self.full_redraw = false;
+                self.previous_dirty.clear();
+                self.previous_dirty.add(video.bounds());
+                self.previous = core::mem::take(&mut self.commands);
+            }
+            RenderMode::Dirty => {
+                self.submit_dirty(video);
+                self.previous = core::mem::take(&mut self.commands);
+            }
+        }
+
+        self.damaged.clear();
+    }
+}
+
+impl Default for RenderQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::{BackendCall, MockBackend};
+
+    fn screen() -> Rect {
+        Rect::new(0.0, 0.0, 100.0, 100.0)
+    }
+
+    fn square(x: f64, y: f64) -> Rect {
+        Rect::new(x, y, x + 10.0, y + 10.0)
+    }
+
+    fn rects(video: &MockBackend) -> Vec<Rect> {
+        video
+            .calls()
+            .iter()
+            .filter_map(|call| match call {
+                BackendCall::DrawRect { rect } => Some(*rect),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn colors(video: &MockBackend) -> usize {
+        video.count(|call| matches!(call, BackendCall::SetPainterColor { .. }))
+    }
+
+    /// A background and a square, the square moved to `x` every frame.
+    fn frame(queue: &mut RenderQueue, video: &mut MockBackend, x: f64) {
+        queue.rect(Layer::Background, screen(), Color::black());
+        queue.rect(Layer::Sprites, square(x, 10.0), Color::white());
+        queue.submit(video);
+    }
+
+    #[test]
+    fn keeps_queued_order_inside_a_layer() {
+        let mut video = MockBackend::new(100.0, 100.0);
+        let mut queue = RenderQueue::new();
+
+        queue.rect(Layer::Sprites, square(0.0, 0.0), Color::red());
+        queue.rect(Layer::Sprites, square(5.0, 5.0), Color::blue());
+        queue.rect(Layer::Sprites, square(10.0, 10.0), Color::red());
+        queue.submit(&mut video);
+
+        assert_eq!(
+            rects(&video),
+            [square(0.0, 0.0), square(5.0, 5.0), square(10.0, 10.0)]
+        );
+        assert_eq!(colors(&video), 3);
+    }
+
+    #[test]
+    fn draws_layers_bottom_up() {
+        let mut video = MockBackend::new(100.0, 100.0);
+        let mut queue = RenderQueue::new();
+
+        queue.rect(Layer::Hud, square(0.0, 0.0), Color::white());
+        queue.rect(Layer::Sprites, square(5.0, 5.0), Color::white());
+        queue.rect(Layer::Background, screen(), Color::black());
+        queue.submit(&mut video);
+
+        assert_eq!(
+            rects(&video),
+            [screen(), square(5.0, 5.0), square(0.0, 0.0)]
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn sets_state_once_for_adjacent_commands() {
+        let mut video = MockBackend::new(100.0, 100.0);
+        let mut queue = RenderQueue::new();
+
+        queue.rect(Layer::Sprites, square(0.0, 0.0), Color::red());
+        queue.rect(Layer::Sprites, square(20.0, 0.0), Color::red());
+        queue.rect(Layer::Sprites, square(40.0, 0.0), Color::blue());
+        queue.submit(&mut video);
+
+        assert_eq!(rects(&video).len(), 3);
+        assert_eq!(colors(&video), 2);
+    }
+
+    #[test]
+    fn draws_text_at_the_baseline() {
+        let mut video = MockBackend::new(100.0, 100.0);
+        let mut queue = RenderQueue::new();
+        let state = PainterState {
+            text_size: Some(10.0),
+            ..PainterState::default()
+        };
+
+        queue.string(Layer::Hud, "HI", Point::new(5.0, 5.0), state);
+        queue.submit(&mut video);
+
+        assert_eq!(
+            video.calls(),
+            [
+                BackendCall::SetPainterTextSize { size: 10.0 },
+                BackendCall::DrawString {
+                    text: String::from("HI"),
+                    position: Point::new(5.0, 15.0),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn full_mode_draws_everything_every_frame() {
+        let mut video = MockBackend::new(100.0, 100.0);
+        let mut queue = RenderQueue::new();
+
+        frame(&mut queue, &mut video, 10.0);
+        frame(&mut queue, &mut video, 10.0);
+
+        assert_eq!(rects(&video).len(), 4);
+    }
+
+    #[test]
+    fn dirty_mode_skips_unchanged_frames() {
+        let mut video = MockBackend::new(100.0, 100.0);
+        let mut queue = RenderQueue::new();
+
+        queue.set_mode(RenderMode::Dirty);
+        frame(&mut queue, &mut video, 10.0);
+
+        assert_eq!(rects(&video), [screen(), square(10.0, 10.0)]);
+
+        // The second buffer still needs the first frame.
+        video.take_calls();
+        frame(&mut queue, &mut video, 10.0);
+
+        assert_eq!(rects(&video), [screen(), square(10.0, 10.0)]);
+
+        video.take_calls();
+        frame(&mut queue, &mut video, 10.0);
+
+        assert!(rects(&video).is_empty());
+    }
+
+    #[test]
+    fn dirty_mode_redraws_what_moved() {
+        let mut video = MockBackend::new(100.0, 100.0);
+        let mut queue = RenderQueue::new();
+
+        queue.set_mode(RenderMode::Dirty);
+        frame(&mut queue, &mut video, 10.0);
+        frame(&mut queue, &mut video, 10.0);
+        video.take_calls();
+        frame(&mut queue, &mut video, 15.0);
+
+        // The background is clipped to the old and new squares, which merged into one region.
+        assert_eq!(
+            rects(&video),
+            [Rect::new(10.0, 10.0, 25.0, 20.0), square(15.0, 10.0)]
+        );
+
+        // The other buffer still has the square at its old position.
+        video.take_calls();
+        frame(&mut queue, &mut video, 15.0);
+
+        assert_eq!(
+            rects(&video),
+            [Rect::new(10.0, 10.0, 25.0, 20.0), square(15.0, 10.0)]
+        );
+    }
+
+    #[test]
+    fn damage_redraws_what_is_under() {
+        let mut video = MockBackend::new(100.0, 100.0);
+        let mut queue = RenderQueue::new();
+
+        queue.set_mode(RenderMode::Dirty);
+        frame(&mut queue, &mut video, 10.0);
+        frame(&mut queue, &mut video, 10.0);
+        video.take_calls();
+        queue.damage(Rect::new(50.0, 50.0, 60.0, 60.0));
+        frame(&mut queue, &mut video, 10.0);
+
+        assert_eq!(rects(&video), [Rect::new(50.0, 50.0, 60.0, 60.0)]);
+    }
+
+    #[test]
+    fn invalidate_redraws_everything() {
+        let mut video = MockBackend::new(100.0, 100.0);
+        let mut queue = RenderQueue::new();
+
+        queue.set_mode(RenderMode::Dirty);
+        frame(&mut queue, &mut video, 10.0);
+        frame(&mut queue, &mut video, 10.0);
+        video.take_calls();
+        queue.invalidate();
+        frame(&mut queue, &mut video, 10.0);
+
+        assert_eq!(rects(&video), [screen(), square(10.0, 10.0)]);
+    }
+}
@@ -0,0 +1,470 @@
+use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+use std::path::Path;
+
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use gpu::{Color, Point, Rect, TextAlign};
+
+use crate::{RenderBackend, PAINTER_STACK_SIZE};
+
+const FONT: &[u8] = include_bytes!("../assets/Greybeard-11px.ttf");
+const BYTES_PER_PIXEL: usize = 4;
+const DEFAULT_TEXT_SIZE: f64 = 12.0;
+
+#[derive(Debug, Clone)]
+enum SoftObject {
+    Text(String),
+    Image {
+        width: usize,
+        height: usize,
+        pixels: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SoftPainter {
+    color: Color,
+    text_size: f64,
+    text_align: TextAlign,
+}
+
+/// Software `RenderBackend` for the host. Everything is rasterized into an RGBA framebuffer,
+/// `flip_buffers` copies it into the presented frame.
+pub struct SoftVideo {
+    width: usize,
+    height: usize,
+    back: Vec<u8>,
+    front: Vec<u8>,
+    painter: SoftPainter,
+    painter_stack: Vec<SoftPainter>,
+    /// Pushes past the full stack, ignored like on the GPU.
+    painter_stack_overflow: usize,
+    objects: BTreeMap<u64, SoftObject>,
+    next_object_id: u64,
+    font: FontRef<'static>,
+    frames: u64,
+}
+
+impl SoftVideo {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            back: vec![0; width * height * BYTES_PER_PIXEL],
+            front: vec![0; width * height * BYTES_PER_PIXEL],
+            painter: SoftPainter {
+                color: Color::white(),
+                text_size: DEFAULT_TEXT_SIZE,
+                text_align: TextAlign::Left,
+            },
+            painter_stack: Vec::with_capacity(PAINTER_STACK_SIZE),
+            painter_stack_overflow: 0,
+            objects: BTreeMap::new(),
+            next_object_id: 1,
+            font: FontRef::try_from_slice(FONT).unwrap(),
+            frames: 0,
+        }
+    }
+
+    /// Last presented frame as RGBA rows.
+    pub fn frame(&self) -> &[u8] {
+        &self.front
+    }
+
+    pub fn frames_count(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        let offset = (y * self.width + x) * BYTES_PER_PIXEL;
+        let pixel = &self.front[offset..offset + BYTES_PER_PIXEL];
+
+        Color::new(pixel[0], pixel[1], pixel[2], pixel[3])
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> image::ImageResult<()> {
+        image::save_buffer(
+            path,
+            &self.front,
+            self.width as u32,
+            self.height as u32,
+            image::ColorType::Rgba8,
+        )
+    }
+
+    pub fn live_objects(&self) -> usize {
+        self.objects.len()
+    }
+
+    fn add_object(&mut self, object: SoftObject) -> u64 {
+        let object_id = self.next_object_id;
+
+        self.next_object_id += 1;
+        self.objects.insert(object_id, object);
+
+        object_id
+    }
+
+    fn blend(&mut self, x: i64, y: i64, color: Color, coverage: f32) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+
+        let offset = (y as usize * self.width + x as usize) * BYTES_PER_PIXEL;
+        let dst = &mut self.back[offset..offset + BYTES_PER_PIXEL];
+        let alpha = color.a as f32 / 255.0 * coverage.clamp(0.0, 1.0);
+        let mix = |src: u8, dst: u8| (src as f32 * alpha + dst as f32 * (1.0 - alpha)) as u8;
+
+        dst[0] = mix(color.r, dst[0]);
+        dst[1] = mix(color.g, dst[1]);
+        dst[2] = mix(color.b, dst[2]);
+        dst[3] = (255.0 * alpha + dst[3] as f32 * (1.0 - alpha)) as u8;
+    }
+
+    fn text_width(&self, text: &str) -> f64 {
+        let font = self
+            .font
+            .as_scaled(PxScale::from(self.painter.text_size as f32));
+
+        text.chars()
+            .map(|ch| font.h_advance(font.glyph_id(ch)) as f64)
+            .sum()
+    }
+}
+
+impl RenderBackend for SoftVideo {
+    fn bounds(&self) -> Rect {
+        Rect::new_from_zero(self.width as f64, self.height as f64)
+    }
+
+    fn create_text_object(&mut self, text: &str) -> Option<u64> {
+        Some(self.add_object(SoftObject::Text(String::from(text))))
+    }
+
+    /// `None` when `data` is shorter than the image.
+    fn create_image(&mut self, data: &[u8], width: u64, height: u64) -> Option<u64> {
+        let (width, height) = (width as usize, height as usize);
+        let pixels = data.get(..width * height * BYTES_PER_PIXEL)?.to_vec();
+
+        Some(self.add_object(SoftObject::Image {
+            width,
+            height,
+            pixels,
+        }))
+    }
+
+    fn delete_object(&mut self, object_id: u64) {
+        self.objects.remove(&object_id);
+    }
+
+    fn push_state(&mut self) {
+        if self.painter_stack.len() >= PAINTER_STACK_SIZE {
+            self.painter_stack_overflow += 1;
+
+            return;
+        }
+
+        self.painter_stack.push(self.painter);
+    }
+
+    fn pop_state(&mut self) {
+        if self.painter_stack_overflow > 0 {
+            self.painter_stack_overflow -= 1;
+
+            return;
+        }
+
+        if let Some(painter) = self.painter_stack.pop() {
+            self.painter = painter;
+        }
+    }
+
+    fn set_painter_color(&mut self, color: Color) {
+        self.painter.color = color;
+    }
+
+    fn get_painter_color(&mut self) -> Color {
+        self.painter.color
+    }
+
+    fn set_painter_text_size(&mut self, size: f64) {
+        self.painter.text_size = size;
+    }
+
+    fn get_painter_text_size(&mut self) -> f64 {
+        self.painter.text_size
+    }
+
+    fn set_painter_text_align(&mut self, align: TextAlign) {
+        self.painter.text_align = align;
+    }
+
+    fn get_painter_text_align(&mut self) -> TextAlign {
+        self.painter.text_align
+    }
+
+    fn draw_rect(&mut self, rect: Rect) {
+        let Point { x, y } = rect.position();
+        let (left, top) = (x as i64, y as i64);
+        let (right, bottom) = ((x + rect.width()) as i64, (y + rect.height()) as i64);
+        let color = self.painter.color;
+
+        for py in top.max(0)..bottom.min(self.height as i64) {
+            for px in left.max(0)..right.min(self.width as i64) {
+                self.blend(px, py, color, 1.0);
+            }
+        }
+    }
+
+    fn draw_string(&mut self, text: &str, position: Point) {
+        let width = self.text_width(text);
+        let scale = PxScale::from(self.painter.text_size as f32);
+        let color = self.painter.color;
+        let mut caret = match self.painter.text_align {
+            TextAlign::Left => position.x,
+            TextAlign::Center => position.x - width / 2.0,
+            TextAlign::Right => position.x - width,
+        } as f32;
+        let mut coverage: Vec<(i64, i64, f32)> = Vec::new();
+        let font = self.font.as_scaled(scale);
+
+        for ch in text.chars() {
+            let glyph_id = font.glyph_id(ch);
+            let glyph = glyph_id.with_scale_and_position(scale, point(caret, position.y as f32));
+
+            caret += font.h_advance(glyph_id);
+
+            if let Some(outlined) = self.font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+
+                outlined.draw(|x, y, value| {
+                    coverage.push((
+                        bounds.min.x as i64 + x as i64,
+                        bounds.min.y as i64 + y as i64,
+                        value,
+                    ))
+                });
+            }
+        }
+
+        for (x, y, value) in coverage {
+            self.blend(x, y, color, value);
+        }
+    }
+
+    fn draw_text(&mut self, object_id: u64, position: Point) {
+        if let Some(SoftObject::Text(text)) = self.objects.get(&object_id).cloned() {
+            self.draw_string(&text, position);
+        }
+    }
+
+    fn measure_string(&mut self, text: &str) -> f64 {
+        self.text_width(text)
+    }
+
+    fn mesaure_text(&mut self, object_id: u64) -> f64 {
+        match self.objects.get(&object_id) {
+            Some(SoftObject::Text(text)) => self.text_width(text),
+            _ => 0.0,
+        }
+    }
+
+    fn draw_image(&mut self, object_id: u64, position: Point) {
+        if let Some(SoftObject::Image { width, height, .. }) = self.objects.get(&object_id) {
+            let dst = Rect::new_from_position(position, *width as f64, *height as f64);
+
+            self.draw_image_rect(object_id, dst);
+        }
+    }
+
+    fn draw_image_rect(&mut self, object_id: u64, dst: Rect) {
+        if let Some(SoftObject::Image { width, height, .. }) = self.objects.get(&object_id) {
+            let src = Rect::new_from_zero(*width as f64, *height as f64);
+
+            self.draw_image_region(object_id, src, dst);
+        }
+    }
+
+    /// Scales `src` into `dst` with nearest neighbour sampling, `src` is clipped to the image.
+    fn draw_image_region(&mut self, object_id: u64, src: Rect, dst: Rect) {
+        let Some(SoftObject::Image {
+            width,
+            height,
+            pixels,
+        }) = self.objects.get(&object_id)
+        else {
+            return;
+        };
+
+        let (width, height) = (*width, *height);
+        let (src_left, src_top) = (src.position().x as usize, src.position().y as usize);
+        let src_right = usize::min(src_left + src.width() as usize, width);
+        let src_bottom = usize::min(src_top + src.height() as usize, height);
+        let Point { x, y } = dst.position();
+        let (dst_width, dst_height) = (dst.width() as i64, dst.height() as i64);
+
+        if src_right <= src_left || src_bottom <= src_top {
+            return;
+        }
+
+        let (src_width, src_height) = (src_right - src_left, src_bottom - src_top);
+        let mut texels = Vec::new();
+
+        for dy in 0..dst_height {
+            for dx in 0..dst_width {
+                let src_x = src_left + (dx as usize * src_width) / dst_width as usize;
+                let src_y = src_top + (dy as usize * src_height) / dst_height as usize;
+                let offset = (src_y * width + src_x) * BYTES_PER_PIXEL;
+                let pixel = &pixels[offset..offset + BYTES_PER_PIXEL];
+
+                texels.push((
+                    x as i64 + dx,
+                    y as i64 + dy,
+                    Color::new(pixel[0], pixel[1], pixel[2], pixel[3]),
+                ));
+            }
+        }
+
+        for (x, y, color) in texels {
+            self.blend(x, y, color, 1.0);
+        }
+    }
+
+    fn flip_buffers(&mut self) {
+        self.front.copy_from_slice(&self.back);
+        self.frames += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::PathBuf};
+
+    use super::*;
+    use crate::{GlyphAtlas, Overflow, TextBox, GREYBEARD};
+
+    /// Set to write the rendered frames over the checked-in snapshots.
+    const UPDATE_ENV: &str = "UPDATE_SNAPSHOTS";
+
+    fn snapshot_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/snapshots")
+            .join(name)
+            .with_extension("png")
+    }
+
+    /// Compares the presented frame with `tests/snapshots/<name>.png`. A mismatching frame is
+    /// saved next to it with a `.new.png` extension.
+    fn assert_snapshot(video: &SoftVideo, name: &str) {
+        let path = snapshot_path(name);
+
+        if env::var_os(UPDATE_ENV).is_some() {
+            video.save_png(&path).unwrap();
+
+            return;
+        }
+
+        let expected = image::open(&path)
+            .unwrap_or_else(|err| panic!("Can't open {}: {err}", path.display()))
+            .to_rgba8();
+        let matches = (expected.width() as usize, expected.height() as usize)
+            == (video.width, video.height)
+            && expected.as_raw() == video.frame();
+
+        if !matches {
+            let actual = path.with_extension("new.png");
+
+            video.save_png(&actual).unwrap();
+            panic!(
+                "Frame differs from {}, saved it to {}, set {UPDATE_ENV} to accept it",
+                path.display(),
+                actual.display()
+            );
+        }
+    }
+
+    /// 2x2 checkerboard next to a solid 2x2 block.
+    fn tiles(video: &mut SoftVideo) -> u64 {
+        let (red, blue, green) = ([255, 0, 0, 255], [0, 0, 255, 255], [0, 255, 0, 255]);
+        let rows = [[red, blue, green, green], [blue, red, green, green]];
+
+        video
+            .create_image(rows.concat().as_flattened(), 4, 2)
+            .unwrap()
+    }
+
+    #[test]
+    fn region_of_a_text_object_keeps_it() {
+        let mut video = SoftVideo::new(8, 8);
+        let text = video.create_text_object("A").unwrap();
+        let area = Rect::new_from_zero(4.0, 4.0);
+
+        video.draw_image_region(text, area, area);
+        video.draw_image_rect(text, area);
+
+        assert_eq!(video.live_objects(), 1);
+        assert!(video.mesaure_text(text) > 0.0);
+    }
+
+    #[test]
+    fn rejects_short_image_data() {
+        let mut video = SoftVideo::new(8, 8);
+
+        assert_eq!(video.create_image(&[0; 15], 2, 2), None);
+        assert!(video.create_image(&[0; 16], 2, 2).is_some());
+    }
+
+    #[test]
+    fn ignores_pushes_past_the_stack() {
+        let mut video = SoftVideo::new(8, 8);
+
+        for _ in 0..=PAINTER_STACK_SIZE {
+            video.push_state();
+        }
+
+        video.set_painter_color(Color::blue());
+        video.pop_state();
+
+        assert_eq!(video.get_painter_color(), Color::blue());
+
+        for _ in 0..PAINTER_STACK_SIZE {
+            video.pop_state();
+        }
+
+        assert_eq!(video.get_painter_color(), Color::white());
+    }
+
+    #[test]
+    fn renders_scene() {
+        let mut video = SoftVideo::new(96, 64);
+        let tiles = tiles(&mut video);
+
+        video.fill_screen(Some(Color::new(0, 0, 64, 255)));
+        video.set_painter_color(Color::new(255, 128, 0, 255));
+        video.draw_rect(Rect::new(4.0, 4.0, 28.0, 20.0));
+        video.set_painter_color(Color::new(255, 255, 255, 128));
+        video.draw_rect(Rect::new(16.0, 12.0, 40.0, 28.0));
+
+        video.draw_image(tiles, Point::new(48.0, 4.0));
+        video.draw_image_rect(tiles, Rect::new(56.0, 4.0, 72.0, 12.0));
+        video.draw_image_region(
+            tiles,
+            Rect::new(2.0, 0.0, 4.0, 2.0),
+            Rect::new(76.0, 4.0, 84.0, 12.0),
+        );
+
+        video.set_painter_color(Color::white());
+        TextBox::new(Rect::new(4.0, 32.0, 60.0, 44.0), 11.0)
+            .with_overflow(Overflow::Ellipsis)
+            .draw("Hello, snapshot!", &mut video);
+        GlyphAtlas::new(GREYBEARD, Color::new(0, 255, 0, 255)).draw_string(
+            &mut video,
+            "OK 42",
+            Point::new(4.0, 60.0),
+            11.0,
+            TextAlign::Left,
+        );
+        video.flip_buffers();
+
+        assert_snapshot(&video, "scene");
+    }
+}
@@ -0,0 +1,260 @@
+use core::time::Duration;
+use gpu::{Color, Rect};
+
+use crate::RenderBackend;
+
+/// Thresholds of a 4x4 ordered dither, blocks with lower values are covered first.
+const DISSOLVE_ORDER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransitionKind {
+    Fade,
+    /// Covers the screen with a growing rect moving in the direction.
+    Wipe(WipeDirection),
+    /// Covers the screen with blocks of the given size in a dither pattern. Blocks are at least
+    /// one pixel.
+    Dissolve(f64),
+}
+
+/// Animated cover drawn over a frame. A transition goes from the frame to the color, a
+/// reversed one goes from the color back to the frame, so a state switch is usually a transition
+/// followed by its reversed copy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    kind: TransitionKind,
+    duration: Duration,
+    color: Color,
+    reversed: bool,
+    start: Duration,
+}
+
+impl Transition {
+    pub const fn new(kind: TransitionKind, duration: Duration) -> Self {
+        Self {
+            kind,
+            duration,
+            color: Color::black(),
+            reversed: false,
+            start: Duration::ZERO,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+
+        self
+    }
+
+    pub fn reversed(mut self) -> Self {
+        self.reversed = !self.reversed;
+
+        self
+    }
+
+    /// Starts the transition at `now`, times are from the firmware clock.
+    pub fn started(mut self, now: Duration) -> Self {
+        self.start = now;
+
+        self
+    }
+
+    pub fn kind(&self) -> TransitionKind {
+        self.kind
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn is_finished(&self, now: Duration) -> bool {
+        now.saturating_sub(self.start) >= self.duration
+    }
+
+    /// How much of the screen is covered, from 0.0 to 1.0.
+    pub fn coverage(&self, now: Duration) -> f64 {
+        let elapsed = now.saturating_sub(self.start).as_secs_f64();
+        let progress = match self.duration.as_secs_f64() {
+            duration if duration > 0.0 => f64::min(elapsed / duration, 1.0),
+            _ => 1.0,
+        };
+
+        if self.reversed {
+            1.0 - progress
+        } else {
+            progress
+        }
+    }
+
+    /// Calls `draw` with every rect covering `bounds` at `now`. For `Fade` it is always one rect
+    /// of the whole `bounds`.
+    pub fn for_each_rect(&self, now: Duration, bounds: Rect, mut draw: impl FnMut(Rect, Color)) {
+        let coverage = self.coverage(now);
+
+        if coverage <= 0.0 {
+            return;
+        }
+
+        let position = bounds.position();
+        let (width, height) = (bounds.width(), bounds.height());
+
+        match self.kind {
+            TransitionKind::Fade => {
+                let mut color = self.color;
+
+                color.a = (color.a as f64 * coverage) as u8;
+                draw(bounds, color);
+            }
+            TransitionKind::Wipe(direction) => {
+                let (x, y) = (position.x, position.y);
+                let rect = match direction {
+                    WipeDirection::Right => Rect::new(x, y, x + width * coverage, y + height),
+                    WipeDirection::Left => {
+                        Rect::new(x + width * (1.0 - coverage), y, x + width, y + height)
+                    }
+                    WipeDirection::Down => Rect::new(x, y, x + width, y + height * coverage),
+                    WipeDirection::Up => {
+                        Rect::new(x, y + height * (1.0 - coverage), x + width, y + height)
+                    }
+                };
+
+                draw(rect, self.color);
+            }
+            TransitionKind::Dissolve(block) => {
+                // Also catches NaN, a zero or negative size would give infinitely many blocks.
+                let block = f64::max(block, 1.0);
+                let threshold = (coverage * 16.0) as u8;
+                let columns = (width / block) as usize + usize::from(width % block > 0.0);
+                let rows = (height / block) as usize + usize::from(height % block > 0.0);
+
+                // Covered blocks next to each other in a row are drawn as one rect.
+                for row in 0..rows {
+                    let mut run_start = None;
+
+                    for column in 0..=columns {
+                        let covered =
+                            column < columns && DISSOLVE_ORDER[row % 4][column % 4] < threshold;
+
+                        match (covered, run_start) {
+                            (true, None) => run_start = Some(column),
+                            (false, Some(start)) => {
+                                let left = position.x + start as f64 * block;
+                                let top = position.y + row as f64 * block;
+                                let right = f64::min(
+                                    left + (column - start) as f64 * block,
+                                    position.x + width,
+                                );
+                                let bottom = f64::min(top + block, position.y + height);
+
+                                draw(Rect::new(left, top, right, bottom), self.color);
+                                run_start = None;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn draw(&self, video: &mut impl RenderBackend, now: Duration) {
+        let bounds = video.bounds();
+
+        video.push_state();
+        self.for_each_rect(now, bounds, |rect, color| {
+            video.set_painter_color(color);
+            video.draw_rect(rect);
+        });
+        video.pop_state();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::{BackendCall, MockBackend};
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn rects(transition: Transition, now: Duration) -> usize {
+        let mut video = MockBackend::new(100.0, 50.0);
+
+        transition.draw(&mut video, now);
+        video.count(|call| matches!(call, BackendCall::DrawRect { .. }))
+    }
+
+    #[test]
+    fn draws_half_a_wipe() {
+        let mut video = MockBackend::new(100.0, 50.0);
+
+        Transition::new(TransitionKind::Wipe(WipeDirection::Left), SECOND)
+            .with_color(Color::white())
+            .draw(&mut video, SECOND / 2);
+
+        assert_eq!(
+            video.take_calls(),
+            vec![
+                BackendCall::PushState,
+                BackendCall::SetPainterColor {
+                    color: Color::white(),
+                },
+                BackendCall::DrawRect {
+                    rect: Rect::new(50.0, 0.0, 100.0, 50.0),
+                },
+                BackendCall::PopState,
+            ]
+        );
+    }
+
+    #[test]
+    fn fades_alpha() {
+        let mut video = MockBackend::new(100.0, 50.0);
+
+        Transition::new(TransitionKind::Fade, SECOND)
+            .reversed()
+            .draw(&mut video, SECOND / 4);
+
+        assert_eq!(
+            video.calls()[1],
+            BackendCall::SetPainterColor {
+                color: Color {
+                    a: 191,
+                    ..Color::black()
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn draws_nothing_when_uncovered() {
+        let transition = Transition::new(TransitionKind::Fade, SECOND);
+
+        assert_eq!(rects(transition, Duration::ZERO), 0);
+        assert_eq!(rects(transition.reversed(), SECOND), 0);
+    }
+
+    #[test]
+    fn dissolve_covers_everything_at_the_end() {
+        let transition = Transition::new(TransitionKind::Dissolve(8.0), SECOND);
+
+        // Covered blocks of a row are merged, 50 px make 7 rows.
+        assert_eq!(rects(transition, SECOND), 7);
+        assert!(rects(transition, SECOND / 2) > 7);
+        assert_eq!(
+            rects(
+                Transition::new(TransitionKind::Dissolve(0.0), SECOND),
+                SECOND
+            ),
+            50
+        );
+    }
+}
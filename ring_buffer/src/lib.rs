@@ -0,0 +1,459 @@
+#![no_std]
+
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+/// Fixed-capacity single-producer/single-consumer queue, meant for passing values from an
+/// interrupt handler to the main loop. Nothing is allocated, so it can live in a `static`.
+///
+/// Indices only grow and wrap around `usize`, a slot is `index % N`. `N` is a power of two, so
+/// the slot stays continuous when an index wraps. The producer owns `tail` and the consumer owns
+/// `head`, except for `Producer::force_push`, which takes the oldest value away from the
+/// consumer with a compare-exchange on `head`. Whoever moves `head` past a value owns its slot
+/// until the slot stamp is released, so a slot is never read and written at the same time.
+pub struct RingBuffer<T: Copy, const N: usize> {
+    slots: [Slot<T>; N],
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    dropped: AtomicUsize,
+    split: AtomicBool,
+}
+
+/// The stamp tells whether the slot is free for an index or holds its value, see `free_stamp`
+/// and `full_stamp`.
+struct Slot<T> {
+    stamp: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+unsafe impl<T: Copy + Send, const N: usize> Sync for RingBuffer<T, N> {}
+
+/// Stamp of a slot that `index` can be written to. Doubled, so with a single slot it doesn't
+/// match the stamp of the previous index being full.
+const fn free_stamp(index: usize) -> usize {
+    index.wrapping_mul(2)
+}
+
+const fn full_stamp(index: usize) -> usize {
+    free_stamp(index) | 1
+}
+
+impl<T: Copy, const N: usize> RingBuffer<T, N> {
+    pub const fn new() -> Self {
+        const { assert!(N > 0, "RingBuffer needs at least one slot") };
+        const {
+            assert!(
+                N.is_power_of_two(),
+                "RingBuffer capacity must be a power of two"
+            )
+        };
+
+        let mut slots = [const {
+            Slot {
+                stamp: AtomicUsize::new(0),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            }
+        }; N];
+        let mut index = 0;
+
+        while index < N {
+            slots[index].stamp = AtomicUsize::new(free_stamp(index));
+            index += 1;
+        }
+
+        Self {
+            slots,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Splits the buffer into its two ends, each one goes to its own context. Only the first call
+    /// gets them, so there is never more than one producer and one consumer.
+    pub fn split(&self) -> Option<(Producer<'_, T, N>, Consumer<'_, T, N>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+
+        Some((Producer { ring: self }, Consumer { ring: self }))
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+
+        usize::min(tail.wrapping_sub(head), N)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+
+    /// Values lost because the buffer was full, either rejected or overwritten.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn slot(&self, index: usize) -> &Slot<T> {
+        &self.slots[index % N]
+    }
+
+    /// Whether the slot of `tail` was released by the reader of its previous value.
+    fn is_free(&self, tail: usize) -> bool {
+        self.slot(tail).stamp.load(Ordering::Acquire) == free_stamp(tail)
+    }
+
+    fn push(&self, value: T) -> Result<(), T> {
+        let tail = self.tail.load(Ordering::Relaxed);
+
+        if !self.is_free(tail) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+
+            return Err(value);
+        }
+
+        self.write(tail, value);
+
+        Ok(())
+    }
+
+    fn force_push(&self, value: T) -> Option<T> {
+        let tail = self.tail.load(Ordering::Relaxed);
+
+        if self.is_free(tail) {
+            self.write(tail, value);
+
+            return None;
+        }
+
+        let oldest = tail.wrapping_sub(N);
+
+        if self
+            .head
+            .compare_exchange(
+                oldest,
+                oldest.wrapping_add(1),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok()
+        {
+            let overwritten = unsafe { (*self.slot(oldest).value.get()).assume_init() };
+
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            self.write(tail, value);
+
+            return Some(overwritten);
+        }
+
+        // The consumer took the oldest value first. The slot is free once it is read, until
+        // then the new value is the one dropped, waiting could deadlock an interrupt handler.
+        if self.is_free(tail) {
+            self.write(tail, value);
+
+            return None;
+        }
+
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+
+        Some(value)
+    }
+
+    fn write(&self, tail: usize, value: T) {
+        let slot = self.slot(tail);
+
+        unsafe {
+            slot.value.get().write(MaybeUninit::new(value));
+        }
+
+        slot.stamp.store(full_stamp(tail), Ordering::Release);
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+    }
+
+    fn pop(&self) -> Option<T> {
+        loop {
+            let head = self.head.load(Ordering::Acquire);
+            let slot = self.slot(head);
+
+            if slot.stamp.load(Ordering::Acquire) != full_stamp(head) {
+                return None;
+            }
+
+            // `force_push` dropped the value first, the next one is tried.
+            if self
+                .head
+                .compare_exchange(
+                    head,
+                    head.wrapping_add(1),
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_err()
+            {
+                continue;
+            }
+
+            let value = unsafe { (*slot.value.get()).assume_init() };
+
+            slot.stamp
+                .store(free_stamp(head.wrapping_add(N)), Ordering::Release);
+
+            return Some(value);
+        }
+    }
+}
+
+impl<T: Copy, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writing end, used by exactly one context, usually an interrupt handler.
+pub struct Producer<'a, T: Copy, const N: usize> {
+    ring: &'a RingBuffer<T, N>,
+}
+
+unsafe impl<T: Copy + Send, const N: usize> Send for Producer<'_, T, N> {}
+
+impl<T: Copy, const N: usize> Producer<'_, T, N> {
+    /// Gives the value back when the buffer is full.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        self.ring.push(value)
+    }
+
+    /// Overwrites the oldest value when the buffer is full and returns it. When the consumer is
+    /// reading the oldest value at that moment, `value` itself is dropped and returned.
+    pub fn force_push(&mut self, value: T) -> Option<T> {
+        self.ring.force_push(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.ring.is_full()
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.ring.dropped()
+    }
+}
+
+/// Reading end, used by exactly one context, usually the main loop.
+pub struct Consumer<'a, T: Copy, const N: usize> {
+    ring: &'a RingBuffer<T, N>,
+}
+
+unsafe impl<T: Copy + Send, const N: usize> Send for Consumer<'_, T, N> {}
+
+impl<T: Copy, const N: usize> Consumer<'_, T, N> {
+    pub fn pop(&mut self) -> Option<T> {
+        self.ring.pop()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.ring.dropped()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Moves an empty buffer to `index` as if that many values went through it.
+    fn start_at<T: Copy, const N: usize>(ring: &RingBuffer<T, N>, index: usize) {
+        ring.head.store(index, Ordering::Relaxed);
+        ring.tail.store(index, Ordering::Relaxed);
+
+        for offset in 0..N {
+            let index = index.wrapping_add(offset);
+
+            ring.slot(index)
+                .stamp
+                .store(free_stamp(index), Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn pops_in_push_order() {
+        let ring = RingBuffer::<u32, 4>::new();
+        let (mut producer, mut consumer) = ring.split().unwrap();
+
+        for value in 1..=3 {
+            producer.push(value).unwrap();
+        }
+
+        assert_eq!(consumer.pop(), Some(1));
+        producer.push(4).unwrap();
+        assert_eq!(consumer.pop(), Some(2));
+        assert_eq!(consumer.pop(), Some(3));
+        assert_eq!(consumer.pop(), Some(4));
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn splits_once() {
+        let ring = RingBuffer::<u32, 4>::new();
+
+        assert!(ring.split().is_some());
+        assert!(ring.split().is_none());
+    }
+
+    #[test]
+    fn full_and_empty_at_capacity() {
+        let ring = RingBuffer::<u32, 4>::new();
+        let (mut producer, mut consumer) = ring.split().unwrap();
+
+        assert!(ring.is_empty());
+        assert!(!ring.is_full());
+
+        for value in 0..4 {
+            producer.push(value).unwrap();
+        }
+
+        assert_eq!(ring.len(), 4);
+        assert!(ring.is_full());
+        assert!(!ring.is_empty());
+
+        while consumer.pop().is_some() {}
+
+        assert!(ring.is_empty());
+        assert!(!ring.is_full());
+    }
+
+    #[test]
+    fn push_rejects_when_full() {
+        let ring = RingBuffer::<u32, 2>::new();
+        let (mut producer, mut consumer) = ring.split().unwrap();
+
+        producer.push(1).unwrap();
+        producer.push(2).unwrap();
+
+        assert_eq!(producer.push(3), Err(3));
+        assert_eq!(ring.dropped(), 1);
+        assert_eq!(consumer.pop(), Some(1));
+        assert_eq!(consumer.pop(), Some(2));
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn force_push_overwrites_oldest() {
+        let ring = RingBuffer::<u32, 2>::new();
+        let (mut producer, mut consumer) = ring.split().unwrap();
+
+        assert_eq!(producer.force_push(1), None);
+        assert_eq!(producer.force_push(2), None);
+        assert_eq!(producer.force_push(3), Some(1));
+        assert_eq!(producer.force_push(4), Some(2));
+
+        assert_eq!(ring.len(), 2);
+        assert_eq!(consumer.pop(), Some(3));
+        assert_eq!(consumer.pop(), Some(4));
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn counts_dropped_values() {
+        let ring = RingBuffer::<u32, 1>::new();
+        let (mut producer, consumer) = ring.split().unwrap();
+
+        producer.push(1).unwrap();
+        let _ = producer.push(2);
+        producer.force_push(3);
+        producer.force_push(4);
+
+        assert_eq!(producer.dropped(), 3);
+        assert_eq!(consumer.dropped(), 3);
+    }
+
+    #[test]
+    fn wraps_around_usize() {
+        let ring = RingBuffer::<u32, 4>::new();
+
+        start_at(&ring, usize::MAX - 1);
+
+        let (mut producer, mut consumer) = ring.split().unwrap();
+
+        for value in 0..4 {
+            producer.push(value).unwrap();
+        }
+
+        assert!(ring.is_full());
+        assert_eq!(producer.push(4), Err(4));
+        assert_eq!(producer.force_push(5), Some(0));
+        assert_eq!(ring.len(), 4);
+
+        assert_eq!(consumer.pop(), Some(1));
+        assert_eq!(consumer.pop(), Some(2));
+        assert_eq!(consumer.pop(), Some(3));
+        assert_eq!(consumer.pop(), Some(5));
+        assert_eq!(consumer.pop(), None);
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn force_push_races_pop() {
+        extern crate std;
+
+        const VALUES: u64 = 200_000;
+        static RING: RingBuffer<(u64, u64), 4> = RingBuffer::new();
+
+        let (mut producer, mut consumer) = RING.split().unwrap();
+        let producer = std::thread::spawn(move || {
+            (0..VALUES)
+                .filter(|value| producer.force_push((*value, !*value)).is_some())
+                .count()
+        });
+        let mut popped = 0;
+        let mut last = None;
+
+        loop {
+            let finished = producer.is_finished();
+
+            while let Some((value, check)) = consumer.pop() {
+                // A torn read would mix two values.
+                assert_eq!(check, !value);
+                assert!(last.is_none_or(|last| last < value));
+
+                last = Some(value);
+                popped += 1;
+            }
+
+            if finished {
+                break;
+            }
+        }
+
+        let lost = producer.join().unwrap();
+
+        assert_eq!(popped + lost, VALUES as usize);
+        assert_eq!(RING.dropped(), lost);
+    }
+}
@@ -1,21 +1,74 @@
+use image::{imageops, EncodableLayout, RgbaImage};
 use std::env;
+use std::fmt::Write;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-macro_rules! build_img_asset {
-    ($filepath:literal) => {
-        use image::EncodableLayout;
+/// Packs sprite sheets into one bitmap with every frame stacked vertically, so a frame is a
+/// contiguous range of rows, and generates `SpriteRect` constants for them. A sheet is split
+/// horizontally into `frames` frames of equal width.
+fn build_atlas(name: &str, sheets: &[(&str, &str, u32)], out_dir: &Path) {
+    let mut frames: Vec<(&str, Vec<RgbaImage>)> = Vec::new();
 
-        let src_filepath = format!("assets/{}.png", $filepath);
+    for (const_name, filepath, frames_count) in sheets {
+        let src_filepath = format!("assets/{}.png", filepath);
         println!("cargo:rerun-if-changed={}", src_filepath);
 
-        let dst_filepath = format!("assets/{}.bitmap", $filepath);
+        let img = image::open(src_filepath).unwrap().to_rgba8();
+        let frame_width = img.width() / frames_count;
+        let sheet_frames = (0..*frames_count)
+            .map(|frame| {
+                imageops::crop_imm(&img, frame * frame_width, 0, frame_width, img.height())
+                    .to_image()
+            })
+            .collect();
 
-        let img = image::open(src_filepath).unwrap();
-        let pixels = img.to_rgba8();
+        frames.push((const_name, sheet_frames));
+    }
 
-        fs::write(dst_filepath, pixels.as_bytes()).unwrap();
-    };
+    let width = frames
+        .iter()
+        .flat_map(|(_, frames)| frames.iter().map(|frame| frame.width()))
+        .max()
+        .unwrap_or(0);
+    let height = frames
+        .iter()
+        .flat_map(|(_, frames)| frames.iter().map(|frame| frame.height()))
+        .sum();
+
+    let mut atlas = RgbaImage::new(width, height);
+    let mut code = String::new();
+    let mut y = 0;
+
+    writeln!(code, "pub const ATLAS_WIDTH: u64 = {width};").unwrap();
+    writeln!(code, "pub const ATLAS_HEIGHT: u64 = {height};").unwrap();
+
+    for (const_name, frames) in &frames {
+        write!(
+            code,
+            "pub const {const_name}: [SpriteRect; {}] = [",
+            frames.len()
+        )
+        .unwrap();
+
+        for frame in frames {
+            imageops::replace(&mut atlas, frame, 0, y as i64);
+            write!(
+                code,
+                "SpriteRect::new({y}, {}, {}), ",
+                frame.width(),
+                frame.height()
+            )
+            .unwrap();
+
+            y += frame.height();
+        }
+
+        writeln!(code, "];").unwrap();
+    }
+
+    fs::write(format!("assets/{}.bitmap", name), atlas.as_bytes()).unwrap();
+    fs::write(out_dir.join(format!("{}.rs", name)), code).unwrap();
 }
 
 fn main() {
@@ -28,5 +81,17 @@ fn main() {
 
     println!("cargo:rerun-if-changed=build.rs");
 
-    build_img_asset!("player");
+    build_atlas(
+        "atlas",
+        &[
+            ("PLAYER", "player", 1),
+            ("INVADER_SQUID", "invader_squid", 2),
+            ("INVADER_CRAB", "invader_crab", 2),
+            ("INVADER_OCTOPUS", "invader_octopus", 2),
+            ("UFO", "ufo", 1),
+            ("BOMB", "bomb", 2),
+            ("DIGITS", "digits", 10),
+        ],
+        &out_dir,
+    );
 }
@@ -0,0 +1,232 @@
+use alloc::vec::Vec;
+use gpu::{Color, Point, Rect};
+use render::{tint_rgba, ColorExt, Layer, RenderBackend, RenderQueue};
+
+use crate::gpu_object::{GpuHandle, GpuObject, SharedGpuObject};
+
+const BYTES_PER_PIXEL: u64 = 4;
+
+/// Part of an atlas object drawn into `bounds`.
+#[derive(Debug, Clone)]
+pub struct Sprite {
+    object: SharedGpuObject,
+    source: Rect,
+    bounds: Rect,
+}
+
+impl Sprite {
+    pub fn with_bounds(mut self, bounds: Rect) -> Self {
+        self.set_bounds(bounds);
+
+        self
+    }
+
+    pub fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+    }
+
+    /// Rect of the sprite inside the atlas.
+    pub fn source(&self) -> Rect {
+        self.source
+    }
+
+    pub fn draw(&self, video: &mut impl RenderBackend) {
+        video.draw_image_region(self.object.id(), self.source, self.bounds);
+    }
+
+    pub fn enqueue(&self, queue: &mut RenderQueue, layer: Layer) {
+        queue.image_region(layer, self.object.id(), self.source, self.bounds);
+    }
+
+    pub fn bounds(&self) -> &Rect {
+        &self.bounds
+    }
+
+    pub fn translate_x(&mut self, x: f64) {
+        self.bounds.translate_x(x);
+    }
+
+    pub fn translate_y(&mut self, y: f64) {
+        self.bounds.translate_y(y);
+    }
+
+    pub fn set_position(&mut self, position: Point) {
+        self.bounds.set_position(position);
+    }
+}
+
+impl GpuHandle for Sprite {
+    fn id(&self) -> u64 {
+        self.object.id()
+    }
+}
+
+/// Rows of the atlas holding one sprite, sprites always start at the left edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpriteRect {
+    pub y: u64,
+    pub width: u64,
+    pub height: u64,
+}
+
+impl SpriteRect {
+    pub const fn new(y: u64, width: u64, height: u64) -> Self {
+        Self { y, width, height }
+    }
+}
+
+pub mod sprites {
+    use super::SpriteRect;
+
+    include!(concat!(env!("OUT_DIR"), "/atlas.rs"));
+}
+
+/// Bitmap with sprites stacked vertically, packed by the build script.
+///
+/// The atlas is uploaded as a single image object and sprites are drawn from their rects of it.
+/// A tinted sprite gets its own small object holding only its pixels, made once per rect and
+/// color, so a few colors don't multiply the whole atlas on the GPU.
+#[derive(Debug)]
+pub struct Atlas {
+    data: &'static [u8],
+    width: u64,
+    height: u64,
+    object: Option<SharedGpuObject>,
+    tinted: Vec<(SpriteRect, [u8; 4], SharedGpuObject)>,
+}
+
+impl Atlas {
+    pub const fn new(data: &'static [u8], width: u64, height: u64) -> Self {
+        Self {
+            data,
+            width,
+            height,
+            object: None,
+            tinted: Vec::new(),
+        }
+    }
+
+    /// `None` when `rect` is out of the atlas bounds or the GPU can't create the atlas, the
+    /// upload is tried again on the next call.
+    pub fn sprite(&mut self, rect: SpriteRect, video: &mut impl RenderBackend) -> Option<Sprite> {
+        if !self.contains(rect) {
+            return None;
+        }
+
+        self.forget_lost_objects(video);
+
+        let object = match self.object {
+            Some(ref object) => object.clone(),
+            None => {
+                let object = GpuObject::create_image(self.data, self.width, self.height, video)?
+                    .into_shared();
+
+                self.object.insert(object).clone()
+            }
+        };
+
+        Some(Sprite {
+            object,
+            source: Rect::new_from_position(
+                Point::new(0.0, rect.y as f64),
+                rect.width as f64,
+                rect.height as f64,
+            ),
+            bounds: Rect::new_from_zero(rect.width as f64, rect.height as f64),
+        })
+    }
+
+    /// Sprite with its pixels multiplied by `tint`. `None` as for `sprite`.
+    pub fn tinted_sprite(
+        &mut self,
+        rect: SpriteRect,
+        tint: Color,
+        video: &mut impl RenderBackend,
+    ) -> Option<Sprite> {
+        let key = tint.to_rgba();
+
+        if key == [u8::MAX; 4] {
+            return self.sprite(rect, video);
+        }
+
+        let pixels = self.pixels(rect)?;
+
+        self.forget_lost_objects(video);
+
+        let object = match self
+            .tinted
+            .iter()
+            .find(|(other, other_key, _)| *other == rect && *other_key == key)
+        {
+            Some((_, _, object)) => object.clone(),
+            None => {
+                let mut data = pixels;
+
+                tint_rgba(&mut data, tint);
+
+                let object =
+                    GpuObject::create_image(&data, rect.width, rect.height, video)?.into_shared();
+
+                self.tinted.push((rect, key, object.clone()));
+
+                object
+            }
+        };
+        let bounds = Rect::new_from_zero(rect.width as f64, rect.height as f64);
+
+        Some(Sprite {
+            object,
+            source: bounds,
+            bounds,
+        })
+    }
+
+    fn contains(&self, rect: SpriteRect) -> bool {
+        rect.width <= self.width && rect.y + rect.height <= self.height
+    }
+
+    /// RGBA pixels of `rect` alone, `None` when it is out of the atlas.
+    fn pixels(&self, rect: SpriteRect) -> Option<Vec<u8>> {
+        if !self.contains(rect) {
+            return None;
+        }
+
+        let row = (self.width * BYTES_PER_PIXEL) as usize;
+        let width = (rect.width * BYTES_PER_PIXEL) as usize;
+        let mut pixels = Vec::with_capacity(width * rect.height as usize);
+
+        for y in rect.y..rect.y + rect.height {
+            let start = y as usize * row;
+
+            pixels.extend_from_slice(self.data.get(start..start + width)?);
+        }
+
+        Some(pixels)
+    }
+
+    /// Objects lost on a reinit are created again.
+    fn forget_lost_objects(&mut self, video: &impl RenderBackend) {
+        if !self
+            .object
+            .as_ref()
+            .is_none_or(|object| object.is_valid(video))
+        {
+            self.object = None;
+        }
+
+        self.tinted.retain(|(_, _, object)| object.is_valid(video));
+    }
+
+    pub fn frames(
+        &mut self,
+        rects: &[SpriteRect],
+        video: &mut impl RenderBackend,
+    ) -> Option<Vec<Sprite>> {
+        rects.iter().map(|rect| self.sprite(*rect, video)).collect()
+    }
+
+    /// The atlas object and the tinted sprites.
+    pub fn objects_count(&self) -> usize {
+        usize::from(self.object.is_some()) + self.tinted.len()
+    }
+}
@@ -1,67 +1,77 @@
 use core::panic::Location;
 
-use gpu::{Color, TextAlign};
+use gpu::{Color, Rect, TextAlign};
+use render::{Overflow, TextBox, GREYBEARD};
 
-use crate::{StackString, Text, Video};
+use crate::{video::degrade_error_policy, StackString, Video};
 
 const FONT_SIZE: f64 = 14.0;
-const TEXT_COLOR: Option<Color> = Some(Color::white());
+const DETAILS_FONT_SIZE: f64 = 12.0;
+const MARGIN: f64 = 16.0;
+const TEXT_COLOR: Color = Color::white();
+const LINE_SPACING: f64 = 2.0;
 const PANIC_TEXT: &str = "FATAL ERROR! PLEASE, RESTART THE MACHINE.";
 
 pub fn bsod(reason: Option<&str>, location: Option<&Location>) -> ! {
     unsafe {
-        let video = Video::mut_video().as_mut().unwrap();
+        // Without a screen there is nowhere to show the error.
+        let Some(video) = Video::mut_video().as_mut() else {
+            loop {
+                riscv::asm::wfi();
+            }
+        };
 
-        video.fill_screen(Some(Color::blue()));
+        // The panic may come from the GPU itself, so failed draws are skipped instead of
+        // escalating into another panic.
+        video.set_error_policy(degrade_error_policy);
+        video.set_canvas(None);
 
-        let mut text_pos = video.bounds().center();
+        video.fill_screen(Some(Color::blue()));
 
-        let panic_text = Text::new_static(PANIC_TEXT, video)
-            .with_color(TEXT_COLOR)
-            .with_size(Some(FONT_SIZE))
-            .with_align(TextAlign::Center)
-            .with_position(text_pos);
+        let bounds = video.bounds();
+        let mut top = bounds.vcenter();
+        // GPU text ops may be what failed, the baked font only needs rects.
+        let gpu_text = video.try_measure_string(PANIC_TEXT).is_ok();
 
-        panic_text.draw(video);
+        video.push_state();
+        video.set_painter_color(TEXT_COLOR);
 
-        text_pos.y += FONT_SIZE;
+        top += draw_text(
+            text_box(bounds, top, FONT_SIZE),
+            PANIC_TEXT,
+            gpu_text,
+            video,
+        );
 
         if let Some(reason) = reason {
-            let reason_text = Text::new_dynamic(reason)
-                .with_color(TEXT_COLOR)
-                .with_size(Some(12.0))
-                .with_align(TextAlign::Center)
-                .with_position(text_pos);
-
-            reason_text.draw(video);
-
-            text_pos.y += FONT_SIZE;
+            top += draw_text(
+                text_box(bounds, top, DETAILS_FONT_SIZE),
+                reason,
+                gpu_text,
+                video,
+            );
         }
 
         if let Some(location) = location {
             let mut msg = StackString::new();
-            let mut file = location.file();
-
-            if file.len() > 15 {
-                file = &file[file.len() - 15..file.len()];
-            }
 
             msg.format(format_args!(
-                "LINE: {}, COLUMN: {}, FILE: ...{}",
+                "LINE: {}, COLUMN: {}, FILE: {}",
                 location.line(),
                 location.column(),
-                file
+                location.file()
             ));
 
-            let text = Text::new_dynamic(msg.str())
-                .with_color(TEXT_COLOR)
-                .with_size(Some(12.0))
-                .with_align(TextAlign::Center)
-                .with_position(text_pos);
-
-            text.draw(video);
+            draw_text(
+                text_box(bounds, top, DETAILS_FONT_SIZE),
+                msg.str(),
+                gpu_text,
+                video,
+            );
         }
 
+        video.pop_state();
+
         video.flip_buffers();
 
         loop {
@@ -69,3 +79,27 @@ pub fn bsod(reason: Option<&str>, location: Option<&Location>) -> ! {
         }
     }
 }
+
+/// Wraps the text between the screen margins, below `top`.
+fn text_box(bounds: Rect, top: f64, size: f64) -> TextBox {
+    let bottom = f64::max(bounds.height() - MARGIN, top);
+
+    TextBox::new(
+        Rect::new(MARGIN, top, bounds.width() - MARGIN, bottom),
+        size,
+    )
+    .with_align(TextAlign::Center)
+    .with_line_spacing(LINE_SPACING)
+    .with_overflow(Overflow::Ellipsis)
+}
+
+/// Returns the height taken by the text.
+fn draw_text(text_box: TextBox, text: &str, gpu_text: bool, video: &mut Video) -> f64 {
+    let layout = if gpu_text {
+        text_box.draw(text, video)
+    } else {
+        text_box.draw_bitmap(text, &GREYBEARD, video)
+    };
+
+    layout.height()
+}
@@ -0,0 +1,62 @@
+use gpu::{Point, Rect};
+
+/// Fixed logical resolution scaled by an integer factor and centered on the screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Canvas {
+    bounds: Rect,
+    scale: f64,
+    offset: Point,
+}
+
+impl Canvas {
+    /// Picks the largest integer scale at which `width`x`height` fits into `screen`.
+    pub fn fit(width: f64, height: f64, screen: Rect) -> Self {
+        let scale = f64::min(screen.width() / width, screen.height() / height) as u64;
+        let scale = u64::max(scale, 1) as f64;
+        let offset = Point::new(
+            ((screen.width() - width * scale) / 2.0) as i64 as f64,
+            ((screen.height() - height * scale) / 2.0) as i64 as f64,
+        );
+
+        Self {
+            bounds: Rect::new_from_zero(width, height),
+            scale,
+            offset,
+        }
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Area of the screen covered by the canvas.
+    pub fn screen_rect(&self) -> Rect {
+        self.to_screen_rect(self.bounds)
+    }
+
+    pub fn to_screen(&self, point: Point) -> Point {
+        Point::new(
+            point.x * self.scale + self.offset.x,
+            point.y * self.scale + self.offset.y,
+        )
+    }
+
+    pub fn to_screen_rect(&self, rect: Rect) -> Rect {
+        Rect::new_from_position(
+            self.to_screen(rect.position()),
+            rect.width() * self.scale,
+            rect.height() * self.scale,
+        )
+    }
+
+    pub fn to_canvas(&self, point: Point) -> Point {
+        Point::new(
+            (point.x - self.offset.x) / self.scale,
+            (point.y - self.offset.y) / self.scale,
+        )
+    }
+}
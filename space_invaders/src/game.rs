@@ -1,9 +1,17 @@
-use core::time::Duration;
+use alloc::vec::Vec;
+use core::{fmt::Write, time::Duration};
 
 use gpu::{Color, Point, Rect, TextAlign};
 use hid::keyboard::KeyboardKey;
+use render::{
+    palette::arcade, DebugOverlay, FrameStats, Layer, RenderBackend, RenderMode, RenderQueue,
+    Transition, TransitionKind,
+};
 
-use crate::{io::IoEvent, Image, Io, Text, Time, Video};
+use crate::{
+    sprites, Action, Atlas, Canvas, InputMap, InputState, Io, ScreenHandle, Sprite, StackString,
+    Text, TextCache, Time, Video, ALLOCATOR,
+};
 
 macro_rules! include_asset {
     ($path:literal) => {
@@ -11,31 +19,59 @@ macro_rules! include_asset {
     };
 }
 
+const CANVAS_WIDTH: f64 = 224.0;
+const CANVAS_HEIGHT: f64 = 256.0;
+const LABEL_SPACING: f64 = 6.0;
+/// Canvas pixels per second.
+const PLAYER_SPEED: f64 = 60.0;
+const INVADER_COLUMNS: usize = 11;
+/// Side of the cell every invader is centered in.
+const INVADER_SPACING: f64 = 16.0;
+const INVADERS_TOP: f64 = 64.0;
+const SCORE_POSITION: Point = Point::new(8.0, 8.0);
+/// Text objects kept for the HUD strings, the score changes rarely compared to the frame rate.
+const TEXT_CACHE_BUDGET: usize = 8;
+const TRANSITION: Transition = Transition::new(TransitionKind::Fade, Duration::from_millis(400));
+
 static mut GAME_TITLE_TEXT_OBJECT: Option<Text<&str>> = None;
 static mut PRESS_ENTER_LABEL: Option<Text<&str>> = None;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Game {
     state: GameState,
+    queue: RenderQueue,
+    atlas: Atlas,
+    overlay: DebugOverlay,
+    text_cache: TextCache,
+    transition: Option<Transition>,
+    input: InputState,
+    controls: InputMap,
+    last_frame: Duration,
+    /// `RenderBackend::generation` the GPU objects were created in.
+    generation: u64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 enum GameState {
-    MainMenu { next_blink_time: Duration },
-    InGame { player: Image },
+    MainMenu {
+        next_blink_time: Duration,
+    },
+    InGame {
+        player: Sprite,
+        invaders: Vec<Sprite>,
+        score: u32,
+    },
 }
 
 impl Game {
-    fn render_main_menu(state: &mut GameState, video: &mut Video) {
+    fn render_main_menu(state: &mut GameState, queue: &mut RenderQueue) {
         let mut now = Time::now();
         let GameState::MainMenu { next_blink_time } = state else {
             unreachable!()
         };
 
-        video.fill_screen(Some(Color::black()));
-
         let title = unsafe { GAME_TITLE_TEXT_OBJECT.as_ref().unwrap() };
-        title.draw(video);
+        title.enqueue(queue, Layer::Hud);
 
         let label = unsafe { PRESS_ENTER_LABEL.as_mut().unwrap() };
 
@@ -46,93 +82,125 @@ impl Game {
             label.set_color(Some(Color::white()));
         }
 
-        label.draw(video);
-
-        video.flip_buffers();
+        label.enqueue(queue, Layer::Hud);
     }
 
-    fn render_in_game(state: &mut GameState, video: &mut Video) {
-        let GameState::InGame { player } = state else {
+    fn render_in_game(
+        state: &mut GameState,
+        queue: &mut RenderQueue,
+        text_cache: &mut TextCache,
+        video: &mut impl RenderBackend,
+    ) {
+        let GameState::InGame {
+            player,
+            invaders,
+            score,
+        } = state
+        else {
             unreachable!()
         };
-
-        video.fill_screen(Some(Color::black()));
-
-        player.draw(video);
-
-        video.flip_buffers();
-    }
-
-    fn render(state: &mut GameState, video: &mut Video) {
-        match state {
-            GameState::MainMenu { .. } => Self::render_main_menu(state, video),
-            GameState::InGame { .. } => Self::render_in_game(state, video),
+        let mut score_text = StackString::new();
+
+        if write!(score_text, "SCORE {score:04}").is_ok() {
+            Text::new_dynamic(score_text.str())
+                .with_size(Some(12.0))
+                .with_color(Some(Color::white()))
+                .with_position(SCORE_POSITION)
+                .enqueue_cached(queue, Layer::Hud, text_cache, video);
         }
-    }
 
-    fn start_new_game(state: &mut GameState, video: &mut Video) {
-        let bounds = video.bounds();
-
-        *state = GameState::InGame {
-            player: Image::new(
-                include_asset!("player.bitmap"),
-                Rect::new_from_zero(11.0, 7.0),
-                video,
-            )
-            .with_bounds(Rect::new_from_position(
-                Point::new(bounds.hcenter(), bounds.height() - 22.0),
-                11.0,
-                7.0,
-            )),
+        for invader in invaders.iter() {
+            invader.enqueue(queue, Layer::Sprites);
         }
+
+        player.enqueue(queue, Layer::Sprites);
     }
 
-    fn handle_main_menu_event(state: &mut GameState, video: &mut Video, ev: IoEvent) {
-        let IoEvent::Keyboard(ev) = ev;
+    fn render(
+        state: &mut GameState,
+        queue: &mut RenderQueue,
+        overlay: &mut DebugOverlay,
+        text_cache: &mut TextCache,
+        transition: &mut Option<Transition>,
+        stats: &FrameStats,
+        video: &mut impl RenderBackend,
+    ) {
+        let now = Time::now();
+
+        queue.set_mode(match state {
+            GameState::MainMenu { .. } => RenderMode::Dirty,
+            GameState::InGame { .. } => RenderMode::Full,
+        });
+        queue.rect(Layer::Background, video.bounds(), Color::black());
 
-        if !matches!(ev.state, hid::KeyState::Down) {
-            return;
+        match state {
+            GameState::MainMenu { .. } => Self::render_main_menu(state, queue),
+            GameState::InGame { .. } => Self::render_in_game(state, queue, text_cache, video),
         }
 
-        if matches!(ev.key, KeyboardKey::Return) {
-            Self::start_new_game(state, video);
+        // The overlay is drawn over the queue and changes every frame.
+        if let Some(panel) = overlay.bounds() {
+            queue.damage(panel);
         }
-    }
 
-    fn handle_in_game_event(state: &mut GameState, video: &mut Video, ev: IoEvent) {
-        let GameState::InGame { player } = state else {
-            unreachable!()
-        };
+        queue.submit(video);
 
-        let IoEvent::Keyboard(ev) = ev;
+        if let Some(current) = transition {
+            current.draw(video, now);
 
-        if matches!(ev.state, hid::KeyState::Up) {
-            return;
+            if current.is_finished(now) {
+                *transition = None;
+            }
         }
 
-        match ev.key {
-            KeyboardKey::Left => player.translate_x(-4.0),
-            KeyboardKey::Right => player.translate_x(4.0),
-            _ => {}
-        }
+        overlay.frame(now);
+        overlay.draw(video, stats);
+        video.flip_buffers();
     }
 
-    fn handle_event(state: &mut GameState, video: &mut Video, ev: IoEvent) {
-        match state {
-            GameState::MainMenu { .. } => Self::handle_main_menu_event(state, video, ev),
-            GameState::InGame { .. } => Self::handle_in_game_event(state, video, ev),
+    /// Shows the hi-score table on every screen but the primary one. It doesn't change, so it is
+    /// drawn into both buffers once.
+    fn render_hi_scores() {
+        let secondary = Video::screens().filter(|handle| *handle != ScreenHandle::PRIMARY);
+
+        for video in secondary.filter_map(|handle| handle.video()) {
+            video.set_canvas(Some(Canvas::fit(
+                CANVAS_WIDTH,
+                CANVAS_HEIGHT,
+                video.screen_bounds(),
+            )));
+
+            let bounds = video.bounds();
+            let title = Text::new_dynamic("HI-SCORE")
+                .with_align(TextAlign::Center)
+                .with_size(Some(24.0))
+                .with_color(Some(Color::white()))
+                .with_position(Point::new(bounds.hcenter(), bounds.height() / 3.0));
+            let title_bounds = title.calc_bounds(video);
+            let score = Text::new_dynamic("0000")
+                .with_align(TextAlign::Center)
+                .with_size(Some(24.0))
+                .with_color(Some(arcade::GREEN))
+                .with_position(Point::new(
+                    title_bounds.hcenter(),
+                    title_bounds.position().y + title_bounds.height() + LABEL_SPACING,
+                ));
+
+            for _ in 0..2 {
+                video.fill_screen(Some(Color::black()));
+                title.draw(video);
+                score.draw(video);
+                video.flip_buffers();
+            }
         }
     }
 
-    pub fn start(mut self) {
-        let video = Video::mut_video().as_mut().unwrap();
-        let io = Io::mut_io().as_mut().unwrap();
-
+    fn create_labels(video: &mut Video) {
         unsafe {
             GAME_TITLE_TEXT_OBJECT = Some(
                 Text::new_static("SPACE INVADERS", video)
                     .with_align(TextAlign::Center)
-                    .with_size(Some(32.0))
+                    .with_size(Some(24.0))
                     .with_color(Some(Color::white()))
                     .with_position(video.bounds().center()),
             );
@@ -142,25 +210,224 @@ impl Game {
             PRESS_ENTER_LABEL = Some(
                 Text::new_static("Press ENTER to start", video)
                     .with_align(TextAlign::Center)
-                    .with_size(Some(14.0))
+                    .with_size(Some(12.0))
                     .with_color(Some(Color::white()))
                     .with_position(video.bounds().center()),
             );
 
-            let mut label_bounds = PRESS_ENTER_LABEL.as_ref().unwrap().calc_bounds(video);
-            label_bounds.translate_y(title_bounds.height() + 12.0);
+            let label_position = Point::new(
+                title_bounds.hcenter(),
+                title_bounds.position().y + title_bounds.height() + LABEL_SPACING,
+            );
 
             PRESS_ENTER_LABEL
                 .as_mut()
                 .unwrap()
-                .set_position(label_bounds.position())
+                .set_position(label_position)
+        }
+    }
+
+    fn player_sprite(atlas: &mut Atlas, video: &mut impl RenderBackend) -> Option<Sprite> {
+        atlas.tinted_sprite(sprites::PLAYER[0], arcade::GREEN, video)
+    }
+
+    /// Invader formation from the top row down, every row gets its own color of `arcade::ROWS`.
+    fn invaders(atlas: &mut Atlas, video: &mut impl RenderBackend) -> Option<Vec<Sprite>> {
+        let rows = [
+            sprites::INVADER_SQUID[0],
+            sprites::INVADER_CRAB[0],
+            sprites::INVADER_CRAB[0],
+            sprites::INVADER_OCTOPUS[0],
+            sprites::INVADER_OCTOPUS[0],
+        ];
+        let left = video.bounds().hcenter() - INVADER_SPACING * INVADER_COLUMNS as f64 / 2.0;
+        let mut invaders = Vec::with_capacity(rows.len() * INVADER_COLUMNS);
+
+        for (row, (rect, color)) in rows.iter().zip(arcade::ROWS.steps(rows.len())).enumerate() {
+            let sprite = atlas.tinted_sprite(*rect, color, video)?;
+            let (width, height) = (rect.width as f64, rect.height as f64);
+
+            for column in 0..INVADER_COLUMNS {
+                let position = Point::new(
+                    left + column as f64 * INVADER_SPACING + (INVADER_SPACING - width) / 2.0,
+                    INVADERS_TOP + row as f64 * INVADER_SPACING,
+                );
+
+                invaders.push(
+                    sprite
+                        .clone()
+                        .with_bounds(Rect::new_from_position(position, width, height)),
+                );
+            }
+        }
+
+        Some(invaders)
+    }
+
+    /// Creates the GPU objects again after they were lost on a reinit of the video.
+    fn recreate_objects(state: &mut GameState, atlas: &mut Atlas, video: &mut Video) {
+        Self::create_labels(video);
+
+        if let GameState::InGame {
+            player, invaders, ..
+        } = state
+        {
+            if let Some(sprite) = Self::player_sprite(atlas, video) {
+                *player = sprite.with_bounds(*player.bounds());
+            }
+
+            if let Some(sprites) = Self::invaders(atlas, video) {
+                for (invader, sprite) in invaders.iter_mut().zip(sprites) {
+                    *invader = sprite.with_bounds(*invader.bounds());
+                }
+            }
+        }
+    }
+
+    /// Stays in the menu when the sprites can't be created.
+    fn start_new_game(state: &mut GameState, atlas: &mut Atlas, video: &mut impl RenderBackend) {
+        let bounds = video.bounds();
+        let (Some(player), Some(invaders)) = (
+            Self::player_sprite(atlas, video),
+            Self::invaders(atlas, video),
+        ) else {
+            return;
+        };
+
+        *state = GameState::InGame {
+            player: player.with_bounds(Rect::new_from_position(
+                Point::new(bounds.hcenter(), bounds.height() - 22.0),
+                sprites::PLAYER[0].width as f64,
+                sprites::PLAYER[0].height as f64,
+            )),
+            invaders,
+            score: 0,
+        }
+    }
+
+    fn handle_main_menu_input(
+        state: &mut GameState,
+        atlas: &mut Atlas,
+        video: &mut impl RenderBackend,
+        input: &InputState,
+        controls: &InputMap,
+    ) {
+        if controls.just_pressed(Action::Confirm, input) {
+            Self::start_new_game(state, atlas, video);
+        }
+    }
+
+    fn handle_in_game_input(
+        state: &mut GameState,
+        video: &mut impl RenderBackend,
+        input: &InputState,
+        controls: &InputMap,
+        elapsed: Duration,
+    ) {
+        let GameState::InGame { player, .. } = state else {
+            unreachable!()
+        };
+
+        let step = PLAYER_SPEED * elapsed.as_secs_f64();
+        let mut x = player.bounds().position().x;
+
+        if controls.is_down(Action::MoveLeft, input) {
+            x -= step;
+        }
+
+        if controls.is_down(Action::MoveRight, input) {
+            x += step;
+        }
+
+        let bounds = video.bounds();
+        let max_x = bounds.position().x + bounds.width() - player.bounds().width();
+        let y = player.bounds().position().y;
+
+        player.set_position(Point::new(f64::clamp(x, bounds.position().x, max_x), y));
+    }
+
+    fn handle_input(
+        state: &mut GameState,
+        atlas: &mut Atlas,
+        video: &mut impl RenderBackend,
+        input: &InputState,
+        controls: &InputMap,
+        elapsed: Duration,
+    ) {
+        match state {
+            GameState::MainMenu { .. } => {
+                Self::handle_main_menu_input(state, atlas, video, input, controls)
+            }
+            GameState::InGame { .. } => {
+                Self::handle_in_game_input(state, video, input, controls, elapsed)
+            }
         }
+    }
+
+    pub fn start(mut self) {
+        let video = Video::mut_video().as_mut().unwrap();
+        let io = Io::mut_io().as_mut().unwrap();
+
+        video.set_canvas(Some(Canvas::fit(
+            CANVAS_WIDTH,
+            CANVAS_HEIGHT,
+            video.screen_bounds(),
+        )));
+
+        Self::create_labels(video);
+        Self::render_hi_scores();
+
+        self.last_frame = Time::now();
+        self.generation = video.generation();
 
         loop {
-            Self::render(&mut self.state, video);
+            if video.generation() != self.generation {
+                self.generation = video.generation();
+                Self::recreate_objects(&mut self.state, &mut self.atlas, video);
+                self.queue.invalidate();
+            }
+
+            let stats = FrameStats {
+                gpu_ops: video.frame_ops(),
+                heap_used: ALLOCATOR.used(),
+                heap_free: ALLOCATOR.free(),
+                queued_events: io.queued_events(),
+            };
+
+            Self::render(
+                &mut self.state,
+                &mut self.queue,
+                &mut self.overlay,
+                &mut self.text_cache,
+                &mut self.transition,
+                &stats,
+                video,
+            );
+
+            self.input.update(io);
+
+            if self.input.just_pressed(KeyboardKey::F3) {
+                self.overlay.toggle();
+                self.queue.invalidate();
+            }
+
+            let now = Time::now();
+            let elapsed = now.saturating_sub(self.last_frame);
+            let in_menu = matches!(self.state, GameState::MainMenu { .. });
+
+            self.last_frame = now;
+
+            Self::handle_input(
+                &mut self.state,
+                &mut self.atlas,
+                video,
+                &self.input,
+                &self.controls,
+                elapsed,
+            );
 
-            if let Some(ev) = io.poll() {
-                Self::handle_event(&mut self.state, video, ev);
+            if in_menu && matches!(self.state, GameState::InGame { .. }) {
+                self.transition = Some(TRANSITION.reversed().started(Time::now()));
             }
         }
     }
@@ -172,6 +439,19 @@ impl Default for Game {
             state: GameState::MainMenu {
                 next_blink_time: Duration::from_secs(0),
             },
+            queue: RenderQueue::new(),
+            atlas: Atlas::new(
+                include_asset!("atlas.bitmap"),
+                sprites::ATLAS_WIDTH,
+                sprites::ATLAS_HEIGHT,
+            ),
+            overlay: DebugOverlay::new(),
+            text_cache: TextCache::new(TEXT_CACHE_BUDGET),
+            transition: None,
+            input: InputState::new(),
+            controls: InputMap::default(),
+            last_frame: Duration::ZERO,
+            generation: 0,
         }
     }
 }
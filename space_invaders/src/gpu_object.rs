@@ -0,0 +1,175 @@
+use alloc::rc::Rc;
+use core::ptr::{addr_of, addr_of_mut};
+use render::RenderBackend;
+
+/// Most objects dropped between two flips. `drop` can't allocate a bigger queue, so objects
+/// dropped past it are leaked on the GPU and counted by `GpuObject::leaked_deletes`.
+pub const MAX_PENDING_DELETES: usize = 256;
+
+static mut PENDING_DELETES: PendingDeletes = PendingDeletes::new();
+
+/// Objects to delete, with the device and the generation they belong to.
+struct PendingDeletes {
+    entries: [(usize, u64, u64); MAX_PENDING_DELETES],
+    len: usize,
+    leaked: usize,
+}
+
+impl PendingDeletes {
+    const fn new() -> Self {
+        Self {
+            entries: [(0, 0, 0); MAX_PENDING_DELETES],
+            len: 0,
+            leaked: 0,
+        }
+    }
+
+    fn push(&mut self, entry: (usize, u64, u64)) {
+        if self.len == MAX_PENDING_DELETES {
+            self.leaked += 1;
+
+            return;
+        }
+
+        self.entries[self.len] = entry;
+        self.len += 1;
+    }
+
+    /// Removes the entries `take` returns true for, the order isn't kept.
+    fn take(&mut self, mut take: impl FnMut(&(usize, u64, u64)) -> bool) {
+        let mut index = 0;
+
+        while index < self.len {
+            if take(&self.entries[index]) {
+                self.len -= 1;
+                self.entries[index] = self.entries[self.len];
+            } else {
+                index += 1;
+            }
+        }
+    }
+}
+
+pub trait GpuHandle {
+    fn id(&self) -> u64;
+}
+
+/// Uniquely owned GPU object. Dropping it schedules the object for deletion, which happens on
+/// the next `Video::flip_buffers` of any screen (or `release_pending` for other backends), so
+/// commands queued earlier in the frame stay valid.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GpuObject {
+    object_id: u64,
+    device: usize,
+    generation: u64,
+}
+
+impl GpuObject {
+    /// Only ids of successful creates are wrapped, dropping the object deletes the id.
+    fn created(object_id: Option<u64>, video: &impl RenderBackend) -> Option<Self> {
+        object_id.map(|object_id| Self {
+            object_id,
+            device: video.device_index(),
+            generation: video.generation(),
+        })
+    }
+
+    /// `None` when the GPU can't create the object.
+    pub fn create_text(text: &str, video: &mut impl RenderBackend) -> Option<Self> {
+        let object_id = video.create_text_object(text);
+
+        Self::created(object_id, video)
+    }
+
+    /// `None` when the GPU can't create the object.
+    pub fn create_image(
+        data: &[u8],
+        width: u64,
+        height: u64,
+        video: &mut impl RenderBackend,
+    ) -> Option<Self> {
+        let object_id = video.create_image(data, width, height);
+
+        Self::created(object_id, video)
+    }
+
+    pub fn device(&self) -> usize {
+        self.device
+    }
+
+    /// Whether the object still exists on `video`, it is lost when the device is reinitialized.
+    pub fn is_valid(&self, video: &impl RenderBackend) -> bool {
+        self.device == video.device_index() && self.generation == video.generation()
+    }
+
+    pub fn into_shared(self) -> SharedGpuObject {
+        SharedGpuObject(Rc::new(self))
+    }
+
+    /// Deletes the pending objects of `video`, the ones of other devices are left alone.
+    /// Objects lost on a reinit are just forgotten.
+    pub fn release_pending(video: &mut impl RenderBackend) {
+        let device = video.device_index();
+        let generation = video.generation();
+        let pending = unsafe { &mut *addr_of_mut!(PENDING_DELETES) };
+
+        pending.take(|(owner, owner_generation, object_id)| {
+            if *owner != device {
+                return false;
+            }
+
+            if *owner_generation == generation {
+                video.delete_object(*object_id);
+            }
+
+            true
+        });
+    }
+
+    /// Forgets the pending objects of devices `is_live` returns false for, they went away
+    /// together with their objects.
+    pub fn forget_pending(is_live: impl Fn(usize) -> bool) {
+        let pending = unsafe { &mut *addr_of_mut!(PENDING_DELETES) };
+
+        pending.take(|(owner, _, _)| !is_live(*owner));
+    }
+
+    /// Objects dropped while `MAX_PENDING_DELETES` were already pending.
+    pub fn leaked_deletes() -> usize {
+        unsafe { (*addr_of!(PENDING_DELETES)).leaked }
+    }
+}
+
+impl GpuHandle for GpuObject {
+    fn id(&self) -> u64 {
+        self.object_id
+    }
+}
+
+impl Drop for GpuObject {
+    fn drop(&mut self) {
+        unsafe {
+            (*addr_of_mut!(PENDING_DELETES)).push((self.device, self.generation, self.object_id));
+        }
+    }
+}
+
+/// Reference counted GPU object, deleted once the last handle is dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedGpuObject(Rc<GpuObject>);
+
+impl SharedGpuObject {
+    pub fn handles(&self) -> usize {
+        Rc::strong_count(&self.0)
+    }
+
+    pub fn is_valid(&self, video: &impl RenderBackend) -> bool {
+        self.0.is_valid(video)
+    }
+}
+
+impl GpuHandle for SharedGpuObject {
+    fn id(&self) -> u64 {
+        self.0.id()
+    }
+}
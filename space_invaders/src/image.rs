@@ -1,18 +1,40 @@
 use gpu::{Point, Rect};
+use render::{Layer, RenderBackend, RenderQueue};
 
-use crate::Video;
+use crate::gpu_object::{GpuHandle, GpuObject, SharedGpuObject};
 
 #[derive(Debug, Clone)]
-pub struct Image {
+pub struct Image<O = GpuObject> {
     bounds: Rect,
-    object_id: u64,
+    object: O,
 }
 
+/// Image whose GPU object is shared, e.g. by many sprites of the same kind.
+pub type SharedImage = Image<SharedGpuObject>;
+
 impl Image {
-    pub fn new(data: &'static [u8], bounds: Rect, video: &mut Video) -> Self {
-        let object_id = video.create_image(data, bounds.width() as u64, bounds.height() as u64);
+    /// `None` when the GPU can't create the image.
+    pub fn new(data: &'static [u8], bounds: Rect, video: &mut impl RenderBackend) -> Option<Self> {
+        let object =
+            GpuObject::create_image(data, bounds.width() as u64, bounds.height() as u64, video)?;
+
+        Some(Self { bounds, object })
+    }
+
+    pub fn into_shared(self) -> SharedImage {
+        Image {
+            bounds: self.bounds,
+            object: self.object.into_shared(),
+        }
+    }
+}
 
-        Self { bounds, object_id }
+impl<O> Image<O>
+where
+    O: GpuHandle,
+{
+    pub fn from_object(object: O, bounds: Rect) -> Self {
+        Self { bounds, object }
     }
 
     pub fn with_bounds(mut self, bounds: Rect) -> Self {
@@ -25,8 +47,12 @@ impl Image {
         self.bounds = bounds;
     }
 
-    pub fn draw(&self, video: &mut Video) {
-        video.draw_image(self.object_id, self.bounds.position());
+    pub fn draw(&self, video: &mut impl RenderBackend) {
+        video.draw_image(self.object.id(), self.bounds.position());
+    }
+
+    pub fn enqueue(&self, queue: &mut RenderQueue, layer: Layer) {
+        queue.image(layer, self.object.id(), self.bounds);
     }
 
     pub fn bounds(&self) -> &Rect {
@@ -45,3 +71,12 @@ impl Image {
         self.bounds.set_position(position);
     }
 }
+
+impl<O> GpuHandle for Image<O>
+where
+    O: GpuHandle,
+{
+    fn id(&self) -> u64 {
+        self.object.id()
+    }
+}
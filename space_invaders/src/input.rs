@@ -0,0 +1,112 @@
+use alloc::{collections::BTreeSet, vec::Vec};
+use hid::{keyboard::KeyboardKey, KeyState};
+
+use crate::io::{Io, IoEvent, KeyboardEvent};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    pub system: bool,
+    /// Toggled by every press of Caps Lock.
+    pub caps_lock: bool,
+}
+
+/// Keyboard state of the current frame, built from the queued events by `update`.
+///
+/// A key pressed and released between two updates is both `just_pressed` and `just_released`,
+/// but not `is_down`. Repeated `Down` events of a held key don't count as new presses.
+#[derive(Debug, Default)]
+pub struct InputState {
+    held: BTreeSet<KeyboardKey>,
+    pressed: BTreeSet<KeyboardKey>,
+    released: BTreeSet<KeyboardKey>,
+    caps_lock: bool,
+    events: Vec<KeyboardEvent>,
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Consumes every queued event, call once per frame before the queries.
+    pub fn update(&mut self, io: &mut Io) {
+        self.pressed.clear();
+        self.released.clear();
+        self.events.clear();
+
+        while let Some(ev) = io.poll() {
+            self.handle_event(ev);
+        }
+    }
+
+    fn handle_event(&mut self, ev: IoEvent) {
+        let IoEvent::Keyboard(ev) = ev;
+
+        match ev.state {
+            KeyState::Down => {
+                if self.held.insert(ev.key) {
+                    self.pressed.insert(ev.key);
+
+                    if ev.key == KeyboardKey::CapsLock {
+                        self.caps_lock = !self.caps_lock;
+                    }
+                }
+            }
+            KeyState::Up => {
+                if self.held.remove(&ev.key) {
+                    self.released.insert(ev.key);
+                }
+            }
+        }
+
+        self.events.push(ev);
+    }
+
+    pub fn is_down(&self, key: KeyboardKey) -> bool {
+        self.held.contains(&key)
+    }
+
+    pub fn just_pressed(&self, key: KeyboardKey) -> bool {
+        self.pressed.contains(&key)
+    }
+
+    pub fn just_released(&self, key: KeyboardKey) -> bool {
+        self.released.contains(&key)
+    }
+
+    pub fn any_down(&self, keys: &[KeyboardKey]) -> bool {
+        keys.iter().any(|key| self.is_down(*key))
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        Modifiers {
+            shift: self.any_down(&[KeyboardKey::LShift, KeyboardKey::RShift]),
+            control: self.any_down(&[KeyboardKey::LControl, KeyboardKey::RControl]),
+            alt: self.any_down(&[KeyboardKey::LAlt, KeyboardKey::RAlt]),
+            system: self.any_down(&[KeyboardKey::LSystem, KeyboardKey::RSystem]),
+            caps_lock: self.caps_lock,
+        }
+    }
+
+    /// Forgets the press of `key` in this frame, so later `just_pressed` queries don't see it.
+    /// The key stays down.
+    pub fn consume(&mut self, key: KeyboardKey) {
+        self.pressed.remove(&key);
+    }
+
+    /// Events consumed by the last update, in arrival order.
+    pub fn events(&self) -> &[KeyboardEvent] {
+        &self.events
+    }
+
+    /// Forgets the held keys, e.g. when the keyboard focus moves elsewhere.
+    pub fn reset(&mut self) {
+        self.held.clear();
+        self.pressed.clear();
+        self.released.clear();
+        self.events.clear();
+    }
+}
@@ -0,0 +1,198 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::mem;
+use hid::{keyboard::KeyboardKey, KeyState};
+
+use crate::InputState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Pause,
+    Confirm,
+    Back,
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::Pause,
+        Action::Confirm,
+        Action::Back,
+    ];
+
+    /// Menu actions are never used together with the game ones, so the two may share keys.
+    pub fn is_menu(&self) -> bool {
+        matches!(self, Action::Confirm | Action::Back)
+    }
+}
+
+/// Keys bound to every action, an action is active when any of its keys is. The default map has
+/// both the arrows and WASD.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputMap {
+    bindings: BTreeMap<Action, Vec<KeyboardKey>>,
+}
+
+impl InputMap {
+    /// Map without any bindings.
+    pub fn new() -> Self {
+        Self {
+            bindings: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_binding(mut self, action: Action, key: KeyboardKey) -> Self {
+        self.bind(action, key);
+
+        self
+    }
+
+    pub fn bind(&mut self, action: Action, key: KeyboardKey) {
+        let keys = self.bindings.entry(action).or_default();
+
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    pub fn unbind(&mut self, action: Action, key: KeyboardKey) {
+        if let Some(keys) = self.bindings.get_mut(&action) {
+            keys.retain(|other| *other != key);
+        }
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.bindings.remove(&action);
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyboardKey] {
+        self.bindings
+            .get(&action)
+            .map_or(&[], |keys| keys.as_slice())
+    }
+
+    /// Actions `key` is bound to.
+    pub fn actions(&self, key: KeyboardKey) -> impl Iterator<Item = Action> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, keys)| keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    pub fn is_down(&self, action: Action, input: &InputState) -> bool {
+        input.any_down(self.keys(action))
+    }
+
+    pub fn just_pressed(&self, action: Action, input: &InputState) -> bool {
+        self.keys(action).iter().any(|key| input.just_pressed(*key))
+    }
+
+    pub fn just_released(&self, action: Action, input: &InputState) -> bool {
+        self.keys(action)
+            .iter()
+            .any(|key| input.just_released(*key))
+    }
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self::new()
+            .with_binding(Action::MoveLeft, KeyboardKey::Left)
+            .with_binding(Action::MoveLeft, KeyboardKey::A)
+            .with_binding(Action::MoveRight, KeyboardKey::Right)
+            .with_binding(Action::MoveRight, KeyboardKey::D)
+            .with_binding(Action::Fire, KeyboardKey::Space)
+            .with_binding(Action::Fire, KeyboardKey::Up)
+            .with_binding(Action::Fire, KeyboardKey::W)
+            .with_binding(Action::Pause, KeyboardKey::Escape)
+            .with_binding(Action::Pause, KeyboardKey::P)
+            .with_binding(Action::Confirm, KeyboardKey::Return)
+            .with_binding(Action::Confirm, KeyboardKey::Space)
+            .with_binding(Action::Back, KeyboardKey::Escape)
+            .with_binding(Action::Back, KeyboardKey::BackSpace)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureResult {
+    Waiting,
+    Bound(KeyboardKey),
+    Cancelled,
+}
+
+/// Rebinds an action to the next pressed key, for a controls menu. `slot` is the index of the
+/// replaced key among the action keys, a slot past the end adds a key. The captured key is taken
+/// away from the other actions of the same kind, see `Action::is_menu`.
+///
+/// The cancel key usually has actions of its own, e.g. Escape is both Pause and Back in the
+/// default map. `poll` consumes the press that ends the capture, so those actions don't fire in
+/// the same frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyCapture {
+    action: Action,
+    slot: usize,
+    cancel_key: KeyboardKey,
+}
+
+impl KeyCapture {
+    /// `cancel_key` stops the capture without binding anything, it can't be bound itself.
+    pub fn new(action: Action, slot: usize, cancel_key: KeyboardKey) -> Self {
+        Self {
+            action,
+            slot,
+            cancel_key,
+        }
+    }
+
+    pub fn cancel_key(&self) -> KeyboardKey {
+        self.cancel_key
+    }
+
+    pub fn action(&self) -> Action {
+        self.action
+    }
+
+    /// Call once per frame after `InputState::update` until it stops `Waiting`, before anything
+    /// else reads the input. The captured or cancelling press is consumed.
+    pub fn poll(&self, input: &mut InputState, map: &mut InputMap) -> CaptureResult {
+        // Repeats of a key held since before the capture don't count.
+        let Some(key) = input
+            .events()
+            .iter()
+            .find(|ev| ev.state == KeyState::Down && input.just_pressed(ev.key))
+            .map(|ev| ev.key)
+        else {
+            return CaptureResult::Waiting;
+        };
+
+        input.consume(key);
+
+        if key == self.cancel_key {
+            return CaptureResult::Cancelled;
+        }
+
+        for action in Action::ALL {
+            if action != self.action && action.is_menu() == self.action.is_menu() {
+                map.unbind(action, key);
+            }
+        }
+
+        let keys = map.bindings.entry(self.action).or_default();
+
+        match keys.get_mut(self.slot) {
+            Some(slot) => *slot = key,
+            None => keys.push(key),
+        }
+
+        // Removing the duplicate first would shift the slots, so it goes after the replace.
+        let mut seen = false;
+
+        keys.retain(|other| *other != key || !mem::replace(&mut seen, true));
+
+        CaptureResult::Bound(key)
+    }
+}
@@ -1,57 +1,92 @@
-use alloc::collections::VecDeque;
+use core::time::Duration;
 use hid::{
     keyboard::{Keyboard, KeyboardKey},
     KeyState,
 };
 use pci::PciBus;
 use plic::Plic;
+use ring_buffer::{Consumer, Producer, RingBuffer};
 
-static mut EVENTS: Option<EventsQueue> = None;
-static mut IO: Option<Io> = None;
+use crate::Time;
 
-type EventsQueue = VecDeque<IoEvent>;
+static EVENTS: RingBuffer<IoEvent, MAX_EVENTS> = RingBuffer::new();
+static mut IO: Option<Io> = None;
+static mut HANDLER: Option<Handler> = None;
 
 const MAX_EVENTS: usize = 32;
 
+type EventsProducer = Producer<'static, IoEvent, MAX_EVENTS>;
+type EventsConsumer = Consumer<'static, IoEvent, MAX_EVENTS>;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum IoEvent {
     Keyboard(KeyboardEvent),
 }
 
+impl IoEvent {
+    /// When the interrupt handler received the event.
+    pub fn time(&self) -> Duration {
+        match self {
+            IoEvent::Keyboard(ev) => ev.time,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct KeyboardEvent {
     pub key: KeyboardKey,
     pub state: KeyState,
+    pub time: Duration,
+}
+
+/// Which event is lost when one arrives into a full queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Keeps the latest input, e.g. for games that care about the current key state.
+    #[default]
+    DropOldest,
+    /// Keeps the events in the order they were typed, e.g. for text input.
+    DropNewest,
 }
 
+/// Events go from the interrupt handler through a lock-free ring buffer, `poll` returns them in
+/// arrival order.
 pub struct Io {
-    keyboard: Keyboard,
+    consumer: EventsConsumer,
 }
 
 impl Io {
-    pub fn init(mut self) {
+    /// Starts queueing the keyboard events, `overflow` picks the event lost to a full queue.
+    pub fn init(overflow: OverflowPolicy) {
+        let (producer, consumer) = EVENTS.split().expect("Io is initialized once");
+        let pci = PciBus::default();
+        let mut keyboard = pci
+            .find_by_id(hid::keyboard::DEVICE_ID)
+            .map(Keyboard::from)
+            .unwrap();
+
         unsafe {
             let mut plic = Plic::default();
-            self.keyboard.set_events(true);
+            keyboard.set_events(true);
 
             plic.set_threshold(0);
-            plic.set_enabled(self.keyboard.device.irq_pin, true);
-            plic.set_priority(self.keyboard.device.irq_pin, u8::MAX);
+            plic.set_enabled(keyboard.device.irq_pin, true);
+            plic.set_priority(keyboard.device.irq_pin, u8::MAX);
+
+            HANDLER = Some(Handler {
+                keyboard,
+                producer,
+                overflow,
+            });
+            IO = Some(Io { consumer });
 
             riscv::interrupt::enable();
             riscv::register::mie::set_mext();
-
-            Self::mut_io().replace(self);
-            Self::mut_queue().replace(VecDeque::new());
         }
     }
 
     pub fn poll(&mut self) -> Option<IoEvent> {
-        if let Some(queue) = Self::mut_queue() {
-            return queue.pop_front();
-        }
-
-        None
+        self.consumer.pop()
     }
 
     pub fn poll_block(&mut self) -> IoEvent {
@@ -66,16 +101,13 @@ impl Io {
         }
     }
 
-    pub fn keyboard(&self) -> &Keyboard {
-        &self.keyboard
-    }
-
-    pub fn mut_queue() -> &'static mut Option<EventsQueue> {
-        unsafe { &mut EVENTS }
+    pub fn queued_events(&self) -> usize {
+        self.consumer.len()
     }
 
-    pub fn queue() -> &'static Option<EventsQueue> {
-        unsafe { &EVENTS }
+    /// Events lost to overflow since init.
+    pub fn dropped_events(&self) -> usize {
+        self.consumer.dropped()
     }
 
     pub fn mut_io() -> &'static mut Option<Io> {
@@ -87,32 +119,32 @@ impl Io {
     }
 }
 
-impl Default for Io {
-    fn default() -> Self {
-        let pci = PciBus::default();
-        let keyboard = pci
-            .find_by_id(hid::keyboard::DEVICE_ID)
-            .map(Keyboard::from)
-            .unwrap();
-
-        Self { keyboard }
-    }
+/// Writing end of the queue, only the interrupt handler touches it after `Io::init`.
+struct Handler {
+    keyboard: Keyboard,
+    producer: EventsProducer,
+    overflow: OverflowPolicy,
 }
 
-unsafe fn handle_keyboard_input(io: &mut Io) {
-    let keyboard = &mut io.keyboard;
-    let key = keyboard.last_changed_key();
-    let key_state = keyboard.key_state(key);
-
-    if let Some(queue) = Io::mut_queue() {
-        if queue.len() >= MAX_EVENTS {
-            return;
-        }
+impl Handler {
+    unsafe fn handle_keyboard_input(&mut self) {
+        let key = self.keyboard.last_changed_key();
+        let key_state = self.keyboard.key_state(key);
 
-        queue.push_front(IoEvent::Keyboard(KeyboardEvent {
+        let ev = IoEvent::Keyboard(KeyboardEvent {
             key,
             state: key_state,
-        }))
+            time: Time::now(),
+        });
+
+        match self.overflow {
+            OverflowPolicy::DropOldest => {
+                self.producer.force_push(ev);
+            }
+            OverflowPolicy::DropNewest => {
+                let _ = self.producer.push(ev);
+            }
+        }
     }
 }
 
@@ -121,11 +153,9 @@ unsafe fn machine_external_handler() {
     let mut plic = Plic::default();
 
     while let Some(irq) = plic.pending_irq() {
-        if let Some(io) = Io::mut_io() {
-            let keyboard = &mut io.keyboard;
-
-            if irq == keyboard.device.irq_pin {
-                handle_keyboard_input(io);
+        if let Some(handler) = &mut HANDLER {
+            if irq == handler.keyboard.device.irq_pin {
+                handler.handle_keyboard_input();
             }
         }
 
@@ -0,0 +1,331 @@
+use alloc::{string::String, vec::Vec};
+use core::{ops::Range, time::Duration};
+use gpu::{Color, Point, Rect, TextAlign};
+use render::{RenderBackend, TextMetrics};
+
+use crate::{
+    text_input::{KeyboardLayout, Motion, TextEvent, TextInput},
+    InputState,
+};
+
+const PADDING: f64 = 2.0;
+const CARET_WIDTH: f64 = 1.0;
+const BLINK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Single line text field. Positions are in chars, not bytes. The text scrolls horizontally to
+/// keep the cursor visible.
+#[derive(Debug, Clone)]
+pub struct LineEditor {
+    text: String,
+    cursor: usize,
+    /// Other end of the selection, the cursor is the moving one.
+    anchor: Option<usize>,
+    max_chars: usize,
+    input: TextInput,
+    rect: Rect,
+    text_size: f64,
+    text_color: Color,
+    background: Color,
+    selection_color: Color,
+    /// X of every char boundary, measured on the next draw when empty.
+    offsets: Vec<f64>,
+    scroll: f64,
+    last_edit: Duration,
+}
+
+impl LineEditor {
+    pub fn new(rect: Rect, text_size: f64, max_chars: usize) -> Self {
+        Self {
+            text: String::new(),
+            cursor: 0,
+            anchor: None,
+            max_chars,
+            input: TextInput::new(),
+            rect,
+            text_size,
+            text_color: Color::white(),
+            background: Color::black(),
+            selection_color: Color::blue(),
+            offsets: Vec::new(),
+            scroll: 0.0,
+            last_edit: Duration::ZERO,
+        }
+    }
+
+    pub fn with_input(mut self, input: TextInput) -> Self {
+        self.input = input;
+
+        self
+    }
+
+    pub fn with_colors(mut self, text: Color, background: Color, selection: Color) -> Self {
+        self.text_color = text;
+        self.background = background;
+        self.selection_color = selection;
+
+        self
+    }
+
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn set_rect(&mut self, rect: Rect) {
+        self.rect = rect;
+    }
+
+    pub fn layout(&self) -> KeyboardLayout {
+        self.input.layout()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text, it is cut to `max_chars` and the cursor goes to the end.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.chars().take(self.max_chars).collect();
+        self.cursor = self.len();
+        self.anchor = None;
+        self.offsets.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.set_text("");
+    }
+
+    pub fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn selection(&self) -> Option<Range<usize>> {
+        match self.anchor {
+            Some(anchor) if anchor != self.cursor => {
+                Some(usize::min(anchor, self.cursor)..usize::max(anchor, self.cursor))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn selected_text(&self) -> &str {
+        match self.selection() {
+            Some(range) => &self.text[self.byte_index(range.start)..self.byte_index(range.end)],
+            None => "",
+        }
+    }
+
+    fn byte_index(&self, index: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(index)
+            .map_or(self.text.len(), |(byte, _)| byte)
+    }
+
+    /// Removes the selected text, returns whether there was any.
+    fn delete_selection(&mut self) -> bool {
+        let Some(range) = self.selection() else {
+            self.anchor = None;
+
+            return false;
+        };
+
+        let bytes = self.byte_index(range.start)..self.byte_index(range.end);
+
+        self.text.replace_range(bytes, "");
+        self.cursor = range.start;
+        self.anchor = None;
+        self.offsets.clear();
+
+        true
+    }
+
+    /// Types `ch` over the selection, nothing happens once the line is full.
+    pub fn insert(&mut self, ch: char) {
+        self.delete_selection();
+
+        if self.len() >= self.max_chars {
+            return;
+        }
+
+        let byte = self.byte_index(self.cursor);
+
+        self.text.insert(byte, ch);
+        self.cursor += 1;
+        self.offsets.clear();
+    }
+
+    pub fn backspace(&mut self) {
+        if self.delete_selection() || self.cursor == 0 {
+            return;
+        }
+
+        self.cursor -= 1;
+        self.text.remove(self.byte_index(self.cursor));
+        self.offsets.clear();
+    }
+
+    pub fn delete(&mut self) {
+        if self.delete_selection() || self.cursor == self.len() {
+            return;
+        }
+
+        self.text.remove(self.byte_index(self.cursor));
+        self.offsets.clear();
+    }
+
+    pub fn move_cursor(&mut self, motion: Motion, select: bool) {
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else if let Some(range) = self.selection() {
+            // Moving without Shift collapses the selection to its side.
+            self.anchor = None;
+
+            match motion {
+                Motion::Left => {
+                    self.cursor = range.start;
+                    return;
+                }
+                Motion::Right => {
+                    self.cursor = range.end;
+                    return;
+                }
+                _ => {}
+            }
+        } else {
+            self.anchor = None;
+        }
+
+        self.cursor = match motion {
+            Motion::Left => self.cursor.saturating_sub(1),
+            Motion::Right => usize::min(self.cursor + 1, self.len()),
+            Motion::Home => 0,
+            Motion::End => self.len(),
+        };
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.cursor = self.len();
+    }
+
+    /// Applies an event, `Submit`, `Cancel` and layout changes are left to the caller.
+    pub fn apply(&mut self, ev: TextEvent) {
+        match ev {
+            TextEvent::Char(ch) => self.insert(ch),
+            TextEvent::Backspace => self.backspace(),
+            TextEvent::Delete => self.delete(),
+            TextEvent::Move { motion, select } => self.move_cursor(motion, select),
+            TextEvent::SelectAll => self.select_all(),
+            TextEvent::Submit | TextEvent::Cancel | TextEvent::LayoutChanged(_) => {}
+        }
+    }
+
+    /// Edits the line with the events of the current frame, call once per frame after
+    /// `InputState::update`. Returns `Submit` or `Cancel` when Enter or Escape was pressed.
+    pub fn update(&mut self, input: &InputState, now: Duration) -> Option<TextEvent> {
+        let mut result = None;
+
+        for ev in self.input.update(input) {
+            match ev {
+                TextEvent::Submit | TextEvent::Cancel => result = Some(ev),
+                ev => self.apply(ev),
+            }
+
+            self.last_edit = now;
+        }
+
+        result
+    }
+
+    fn measure(&mut self, video: &mut impl RenderBackend) {
+        if !self.offsets.is_empty() {
+            return;
+        }
+
+        self.offsets.push(0.0);
+
+        for (byte, ch) in self.text.char_indices() {
+            let end = byte + ch.len_utf8();
+
+            self.offsets.push(video.measure_string(&self.text[..end]));
+        }
+    }
+
+    /// Draws the field, the caret blinks while nothing is typed.
+    pub fn draw(&mut self, video: &mut impl RenderBackend, now: Duration) {
+        video.push_state();
+        video.set_painter_text_size(self.text_size);
+        video.set_painter_text_align(TextAlign::Left);
+        self.measure(video);
+
+        let width = self.rect.width() - PADDING * 2.0;
+        let cursor_x = self.offsets[self.cursor];
+
+        if cursor_x - self.scroll > width - CARET_WIDTH {
+            self.scroll = cursor_x - width + CARET_WIDTH;
+        } else if cursor_x < self.scroll {
+            self.scroll = cursor_x;
+        }
+
+        let metrics = TextMetrics::new(0.0, self.text_size);
+        let left = self.rect.position().x + PADDING;
+        let top = self.rect.position().y + (self.rect.height() - metrics.height()) / 2.0;
+        let x = |offset: f64| left + f64::clamp(offset - self.scroll, 0.0, width);
+
+        video.set_painter_color(self.background);
+        video.draw_rect(self.rect);
+
+        if let Some(range) = self.selection() {
+            video.set_painter_color(self.selection_color);
+            video.draw_rect(Rect::new(
+                x(self.offsets[range.start]),
+                top,
+                x(self.offsets[range.end]),
+                top + metrics.height(),
+            ));
+        }
+
+        // Only the chars fully inside the field are drawn.
+        let first = self
+            .offsets
+            .iter()
+            .position(|offset| *offset >= self.scroll)
+            .unwrap_or(0);
+        let last = self
+            .offsets
+            .iter()
+            .rposition(|offset| *offset - self.scroll <= width)
+            .unwrap_or(first);
+
+        if last > first {
+            let text = &self.text[self.byte_index(first)..self.byte_index(last)];
+
+            video.set_painter_color(self.text_color);
+            video.draw_string(
+                text,
+                metrics.baseline(Point::new(x(self.offsets[first]), top)),
+            );
+        }
+
+        let blink = now.saturating_sub(self.last_edit).as_millis() / BLINK_INTERVAL.as_millis();
+
+        if blink.is_multiple_of(2) {
+            video.set_painter_color(self.text_color);
+            video.draw_rect(Rect::new_from_position(
+                Point::new(x(cursor_x), top),
+                CARET_WIDTH,
+                metrics.height(),
+            ));
+        }
+
+        video.pop_state();
+    }
+}
@@ -6,12 +6,21 @@
 
 extern crate alloc;
 
+mod atlas;
 mod bsod;
+mod canvas;
 mod game;
+mod gpu_object;
 mod image;
+mod input;
+mod input_map;
 mod io;
+mod line_editor;
 mod stack_string;
 mod text;
+mod text_cache;
+mod text_input;
+mod tilemap;
 mod time;
 mod video;
 
@@ -26,12 +35,21 @@ use riscv::register::{
 };
 use riscv_rt::entry;
 
-pub use image::Image;
-pub use io::Io;
+pub use atlas::{sprites, Atlas, Sprite, SpriteRect};
+pub use canvas::Canvas;
+pub use gpu_object::{GpuHandle, GpuObject, SharedGpuObject};
+pub use image::{Image, SharedImage};
+pub use input::{InputState, Modifiers};
+pub use input_map::{Action, CaptureResult, InputMap, KeyCapture};
+pub use io::{Io, OverflowPolicy};
+pub use line_editor::LineEditor;
 pub use stack_string::StackString;
 pub use text::Text;
+pub use text_cache::TextCache;
+pub use text_input::{KeyboardLayout, LayoutHotkey, Motion, TextEvent, TextInput};
+pub use tilemap::Tilemap;
 pub use time::Time;
-pub use video::Video;
+pub use video::{ScreenHandle, Video, VideoError};
 
 extern "C" {
     static _sheap: u8;
@@ -67,11 +85,11 @@ unsafe fn panic(info: &core::panic::PanicInfo) -> ! {
 unsafe fn main() -> ! {
     riscv::register::mstatus::set_fs(FS::Initial);
 
-    Video::default().init();
+    Video::init_all();
 
     init_heap();
 
-    Io::default().init();
+    Io::init(OverflowPolicy::DropOldest);
 
     Game::default().start();
 
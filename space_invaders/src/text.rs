@@ -1,8 +1,12 @@
 use gpu::{Color, Point, Rect, TextAlign};
+use render::{Layer, PainterState, RenderBackend, RenderQueue, TextMetrics};
 
-use crate::Video;
+use crate::{
+    gpu_object::{GpuHandle, GpuObject},
+    TextCache,
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Text<T>
 where
     T: AsRef<str>,
@@ -14,12 +18,12 @@ where
     position: Point,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 enum TextType<T>
 where
     T: AsRef<str>,
 {
-    Static(u64),
+    Static(GpuObject),
     Dynamic(T),
 }
 
@@ -37,9 +41,15 @@ where
         }
     }
 
-    pub fn new_static(text: T, video: &mut Video) -> Self {
+    /// Falls back to a dynamic text when the GPU can't create the object.
+    pub fn new_static(text: T, video: &mut impl RenderBackend) -> Self {
+        let text = match GpuObject::create_text(text.as_ref(), video) {
+            Some(object) => TextType::Static(object),
+            None => TextType::Dynamic(text),
+        };
+
         Self {
-            text: TextType::Static(video.create_text_object(text.as_ref())),
+            text,
             color: None,
             size: None,
             align: TextAlign::Left,
@@ -47,83 +57,98 @@ where
         }
     }
 
-    pub fn mesaure_width(&self, video: &mut Video) -> f64 {
-        let mut old_size: Option<f64> = None;
+    pub fn mesaure_width(&self, video: &mut impl RenderBackend) -> f64 {
+        video.push_state();
 
         if let Some(size) = self.size {
-            old_size = Some(video.swap_text_size(size));
+            video.set_painter_text_size(size);
         }
 
         let width = match self.text {
-            TextType::Static(object_id) => video.mesaure_text(object_id),
+            TextType::Static(ref object) => video.mesaure_text(object.id()),
             TextType::Dynamic(ref text) => video.measure_string(text.as_ref()),
         };
 
-        if let Some(old_size) = old_size {
-            video.set_painter_text_size(old_size);
-        }
+        video.pop_state();
 
         width
     }
 
-    pub fn measure_height(&self, video: &mut Video) -> f64 {
-        let mut old_size: Option<f64> = None;
-        let current_size;
+    pub fn measure_height(&self, video: &mut impl RenderBackend) -> f64 {
+        self.metrics(video).height()
+    }
 
-        if let Some(size) = self.size {
-            current_size = video.swap_text_size(size);
-            old_size = Some(current_size);
-        } else {
-            current_size = video.get_painter_text_size();
-        }
+    pub fn metrics(&self, video: &mut impl RenderBackend) -> TextMetrics {
+        let size = self.size.unwrap_or_else(|| video.get_painter_text_size());
 
-        if let Some(old_size) = old_size {
-            video.set_painter_text_size(old_size);
-        }
+        TextMetrics::new(self.mesaure_width(video), size)
+    }
 
-        current_size
+    /// Bounds of the drawn text, the position is the left edge, center or right edge of it
+    /// depending on the align.
+    pub fn calc_bounds(&self, video: &mut impl RenderBackend) -> Rect {
+        self.metrics(video).bounds(self.position, self.align)
     }
 
-    pub fn calc_bounds(&self, video: &mut Video) -> Rect {
-        Rect::new(
-            self.position.x,
-            self.position.y,
-            self.position.x + self.mesaure_width(video),
-            self.position.y + self.measure_height(video),
-        )
+    pub fn baseline(&self, video: &mut impl RenderBackend) -> Point {
+        let size = self.size.unwrap_or_else(|| video.get_painter_text_size());
+
+        TextMetrics::new(0.0, size).baseline(self.position)
     }
 
-    pub fn draw(&self, video: &mut Video) {
-        let mut position = self.position;
-        let mut old_color: Option<Color> = None;
-        let mut old_size: Option<f64> = None;
-        let old_align = video.swap_painter_text_align(self.align);
+    pub fn draw(&self, video: &mut impl RenderBackend) {
+        video.push_state();
+        video.set_painter_text_align(self.align);
 
         if let Some(color) = self.color {
-            old_color = Some(video.swap_painter_color(color));
+            video.set_painter_color(color);
         }
 
         if let Some(size) = self.size {
-            old_size = Some(video.swap_text_size(size));
+            video.set_painter_text_size(size);
         }
 
-        let text_size = old_size.unwrap_or_else(|| video.get_painter_text_size());
-        position.y += text_size;
+        let position = TextMetrics::new(0.0, video.get_painter_text_size()).baseline(self.position);
 
         match self.text {
-            TextType::Static(object_id) => video.draw_text(object_id, position),
+            TextType::Static(ref object) => video.draw_text(object.id(), position),
             TextType::Dynamic(ref text) => video.draw_string(text.as_ref(), position),
         };
 
-        if let Some(old_color) = old_color {
-            video.set_painter_color(old_color);
-        }
+        video.pop_state();
+    }
+
+    pub fn enqueue(&self, queue: &mut RenderQueue, layer: Layer) {
+        let state = PainterState {
+            color: self.color,
+            text_size: self.size,
+            text_align: Some(self.align),
+        };
 
-        if let Some(old_size) = old_size {
-            video.set_painter_text_size(old_size);
+        match self.text {
+            TextType::Static(ref object) => queue.text(layer, object.id(), self.position, state),
+            TextType::Dynamic(ref text) => queue.string(layer, text.as_ref(), self.position, state),
         }
+    }
 
-        video.set_painter_text_align(old_align);
+    /// Same as `enqueue`, dynamic text is queued as a text object once `cache` has one for it.
+    pub fn enqueue_cached(
+        &self,
+        queue: &mut RenderQueue,
+        layer: Layer,
+        cache: &mut TextCache,
+        video: &mut impl RenderBackend,
+    ) {
+        let TextType::Dynamic(ref text) = self.text else {
+            return self.enqueue(queue, layer);
+        };
+        let state = PainterState {
+            color: self.color,
+            text_size: self.size,
+            text_align: Some(self.align),
+        };
+
+        cache.enqueue(queue, layer, text.as_ref(), self.position, state, video);
     }
 
     pub fn with_color(mut self, color: Option<Color>) -> Self {
@@ -166,10 +191,7 @@ where
         self.position = position;
     }
 
-    pub fn dispose(&self, video: &mut Video) {
-        match self.text {
-            TextType::Static(object_id) => video.delete_object(object_id),
-            TextType::Dynamic(_) => {}
-        }
+    pub fn position(&self) -> Point {
+        self.position
     }
 }
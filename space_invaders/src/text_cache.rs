@@ -0,0 +1,230 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use gpu::Point;
+use render::{Layer, PainterState, RenderBackend, RenderQueue};
+
+use crate::gpu_object::{GpuHandle, GpuObject};
+
+/// Strings remembered per text object, the ones without an object are candidates.
+const ENTRIES_PER_OBJECT: usize = 4;
+const DEFAULT_PROMOTE_AFTER: u32 = 2;
+
+#[derive(Debug)]
+struct CacheEntry {
+    object: Option<GpuObject>,
+    uses: u32,
+    last_used: u64,
+    /// Widths by text size bits.
+    widths: Vec<(u64, f64)>,
+}
+
+/// Turns dynamic strings that keep being drawn into text objects, so they are sent to the GPU
+/// once. Holds at most `budget` objects, the least recently used one is dropped for a new one.
+#[derive(Debug)]
+pub struct TextCache {
+    entries: BTreeMap<String, CacheEntry>,
+    budget: usize,
+    objects: usize,
+    promote_after: u32,
+    tick: u64,
+    /// `RenderBackend::generation` the objects were created in.
+    generation: u64,
+}
+
+impl TextCache {
+    pub const fn new(budget: usize) -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            budget,
+            objects: 0,
+            promote_after: DEFAULT_PROMOTE_AFTER,
+            tick: 0,
+            generation: 0,
+        }
+    }
+
+    /// Number of uses after which a string gets a text object.
+    pub fn with_promote_after(mut self, uses: u32) -> Self {
+        self.promote_after = uses;
+
+        self
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+
+        while self.objects > self.budget {
+            self.evict_object();
+        }
+
+        while self.entries.len() > self.max_entries() {
+            self.evict_entry();
+        }
+    }
+
+    pub fn objects_count(&self) -> usize {
+        self.objects
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drops every object, they are deleted on the next flip.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.objects = 0;
+    }
+
+    /// Forgets the objects lost on a reinit of `video`, they are created again on later uses.
+    fn forget_lost_objects(&mut self, video: &impl RenderBackend) {
+        if self.generation == video.generation() {
+            return;
+        }
+
+        for entry in self.entries.values_mut() {
+            entry.object = None;
+        }
+
+        self.objects = 0;
+        self.generation = video.generation();
+    }
+
+    fn max_entries(&self) -> usize {
+        self.budget * ENTRIES_PER_OBJECT
+    }
+
+    fn least_recent(&self, with_object: bool) -> Option<String> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| !with_object || entry.object.is_some())
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(text, _)| text.clone())
+    }
+
+    fn evict_object(&mut self) {
+        if let Some(text) = self.least_recent(true) {
+            if let Some(entry) = self.entries.get_mut(&text) {
+                entry.object = None;
+                entry.uses = 0;
+                self.objects -= 1;
+            }
+        }
+    }
+
+    fn evict_entry(&mut self) {
+        if let Some(text) = self.least_recent(false) {
+            if let Some(entry) = self.entries.remove(&text) {
+                if entry.object.is_some() {
+                    self.objects -= 1;
+                }
+            }
+        }
+    }
+
+    fn touch(&mut self, text: &str) -> &mut CacheEntry {
+        self.tick += 1;
+
+        if !self.entries.contains_key(text) {
+            if self.entries.len() >= self.max_entries() {
+                self.evict_entry();
+            }
+
+            self.entries.insert(
+                String::from(text),
+                CacheEntry {
+                    object: None,
+                    uses: 0,
+                    last_used: 0,
+                    widths: Vec::new(),
+                },
+            );
+        }
+
+        let entry = self.entries.get_mut(text).unwrap();
+
+        entry.last_used = self.tick;
+
+        entry
+    }
+
+    /// Counts a use of `text` and returns its text object once it has one.
+    pub fn object(&mut self, text: &str, video: &mut impl RenderBackend) -> Option<u64> {
+        if self.budget == 0 {
+            return None;
+        }
+
+        self.forget_lost_objects(video);
+
+        let promote_after = self.promote_after;
+        let entry = self.touch(text);
+
+        entry.uses = entry.uses.saturating_add(1);
+
+        if let Some(ref object) = entry.object {
+            return Some(object.id());
+        }
+
+        if entry.uses < promote_after {
+            return None;
+        }
+
+        if self.objects >= self.budget {
+            self.evict_object();
+        }
+
+        let object = GpuObject::create_text(text, video)?;
+        let object_id = object.id();
+
+        // Eviction doesn't touch the entry itself, it is the most recent one.
+        if let Some(entry) = self.entries.get_mut(text) {
+            entry.object = Some(object);
+            self.objects += 1;
+        }
+
+        Some(object_id)
+    }
+
+    /// Width of `text` at the current painter text size, measured once per size.
+    pub fn measure(&mut self, text: &str, video: &mut impl RenderBackend) -> f64 {
+        self.forget_lost_objects(video);
+
+        let size = video.get_painter_text_size().to_bits();
+        let entry = self.touch(text);
+
+        if let Some((_, width)) = entry.widths.iter().find(|(key, _)| *key == size) {
+            return *width;
+        }
+
+        let width = match entry.object {
+            Some(ref object) => video.mesaure_text(object.id()),
+            None => video.measure_string(text),
+        };
+
+        entry.widths.push((size, width));
+
+        width
+    }
+
+    pub fn enqueue(
+        &mut self,
+        queue: &mut RenderQueue,
+        layer: Layer,
+        text: &str,
+        position: Point,
+        state: PainterState,
+        video: &mut impl RenderBackend,
+    ) {
+        match self.object(text, video) {
+            Some(object_id) => queue.text(layer, object_id, position, state),
+            None => queue.string(layer, text, position, state),
+        }
+    }
+}
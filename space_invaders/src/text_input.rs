@@ -0,0 +1,313 @@
+use alloc::vec::Vec;
+use core::mem;
+use hid::{keyboard::KeyboardKey, KeyState};
+
+use crate::{io::KeyboardEvent, InputState, Modifiers};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyboardLayout {
+    /// QWERTY.
+    #[default]
+    English,
+    /// ЙЦУКЕН.
+    Russian,
+}
+
+impl KeyboardLayout {
+    pub fn next(self) -> Self {
+        match self {
+            KeyboardLayout::English => KeyboardLayout::Russian,
+            KeyboardLayout::Russian => KeyboardLayout::English,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyboardLayout::English => "EN",
+            KeyboardLayout::Russian => "RU",
+        }
+    }
+
+    /// Character typed by `key`. Caps Lock only affects letters, Shift inverts it.
+    pub fn translate(&self, key: KeyboardKey, shift: bool, caps_lock: bool) -> Option<char> {
+        let (normal, shifted) = match self {
+            KeyboardLayout::English => english_chars(key),
+            KeyboardLayout::Russian => russian_chars(key),
+        }
+        .or_else(|| common_chars(key))?;
+
+        let upper = if normal.is_alphabetic() {
+            shift != caps_lock
+        } else {
+            shift
+        };
+
+        Some(if upper { shifted } else { normal })
+    }
+}
+
+/// Keys typing the same in every layout.
+fn common_chars(key: KeyboardKey) -> Option<(char, char)> {
+    let chars = match key {
+        KeyboardKey::Space => (' ', ' '),
+        KeyboardKey::Numpad0 => ('0', '0'),
+        KeyboardKey::Numpad1 => ('1', '1'),
+        KeyboardKey::Numpad2 => ('2', '2'),
+        KeyboardKey::Numpad3 => ('3', '3'),
+        KeyboardKey::Numpad4 => ('4', '4'),
+        KeyboardKey::Numpad5 => ('5', '5'),
+        KeyboardKey::Numpad6 => ('6', '6'),
+        KeyboardKey::Numpad7 => ('7', '7'),
+        KeyboardKey::Numpad8 => ('8', '8'),
+        KeyboardKey::Numpad9 => ('9', '9'),
+        KeyboardKey::Add => ('+', '+'),
+        KeyboardKey::Subtract => ('-', '-'),
+        KeyboardKey::Multiply => ('*', '*'),
+        KeyboardKey::Divide => ('/', '/'),
+        KeyboardKey::Equal => ('=', '+'),
+        KeyboardKey::Dash => ('-', '_'),
+        KeyboardKey::Num0 => ('0', ')'),
+        KeyboardKey::Num1 => ('1', '!'),
+        KeyboardKey::Num5 => ('5', '%'),
+        KeyboardKey::Num8 => ('8', '*'),
+        KeyboardKey::Num9 => ('9', '('),
+        _ => return None,
+    };
+
+    Some(chars)
+}
+
+fn english_chars(key: KeyboardKey) -> Option<(char, char)> {
+    let chars = match key {
+        KeyboardKey::A => ('a', 'A'),
+        KeyboardKey::B => ('b', 'B'),
+        KeyboardKey::C => ('c', 'C'),
+        KeyboardKey::D => ('d', 'D'),
+        KeyboardKey::E => ('e', 'E'),
+        KeyboardKey::F => ('f', 'F'),
+        KeyboardKey::G => ('g', 'G'),
+        KeyboardKey::H => ('h', 'H'),
+        KeyboardKey::I => ('i', 'I'),
+        KeyboardKey::J => ('j', 'J'),
+        KeyboardKey::K => ('k', 'K'),
+        KeyboardKey::L => ('l', 'L'),
+        KeyboardKey::M => ('m', 'M'),
+        KeyboardKey::N => ('n', 'N'),
+        KeyboardKey::O => ('o', 'O'),
+        KeyboardKey::P => ('p', 'P'),
+        KeyboardKey::Q => ('q', 'Q'),
+        KeyboardKey::R => ('r', 'R'),
+        KeyboardKey::S => ('s', 'S'),
+        KeyboardKey::T => ('t', 'T'),
+        KeyboardKey::U => ('u', 'U'),
+        KeyboardKey::V => ('v', 'V'),
+        KeyboardKey::W => ('w', 'W'),
+        KeyboardKey::X => ('x', 'X'),
+        KeyboardKey::Y => ('y', 'Y'),
+        KeyboardKey::Z => ('z', 'Z'),
+        KeyboardKey::Num2 => ('2', '@'),
+        KeyboardKey::Num3 => ('3', '#'),
+        KeyboardKey::Num4 => ('4', '$'),
+        KeyboardKey::Num6 => ('6', '^'),
+        KeyboardKey::Num7 => ('7', '&'),
+        KeyboardKey::LBracket => ('[', '{'),
+        KeyboardKey::RBracket => (']', '}'),
+        KeyboardKey::SemiColon => (';', ':'),
+        KeyboardKey::Quote => ('\'', '"'),
+        KeyboardKey::Comma => (',', '<'),
+        KeyboardKey::Period => ('.', '>'),
+        KeyboardKey::Slash => ('/', '?'),
+        KeyboardKey::BackSlash => ('\\', '|'),
+        KeyboardKey::Tilde => ('`', '~'),
+        _ => return None,
+    };
+
+    Some(chars)
+}
+
+fn russian_chars(key: KeyboardKey) -> Option<(char, char)> {
+    let chars = match key {
+        KeyboardKey::Q => ('й', 'Й'),
+        KeyboardKey::W => ('ц', 'Ц'),
+        KeyboardKey::E => ('у', 'У'),
+        KeyboardKey::R => ('к', 'К'),
+        KeyboardKey::T => ('е', 'Е'),
+        KeyboardKey::Y => ('н', 'Н'),
+        KeyboardKey::U => ('г', 'Г'),
+        KeyboardKey::I => ('ш', 'Ш'),
+        KeyboardKey::O => ('щ', 'Щ'),
+        KeyboardKey::P => ('з', 'З'),
+        KeyboardKey::LBracket => ('х', 'Х'),
+        KeyboardKey::RBracket => ('ъ', 'Ъ'),
+        KeyboardKey::A => ('ф', 'Ф'),
+        KeyboardKey::S => ('ы', 'Ы'),
+        KeyboardKey::D => ('в', 'В'),
+        KeyboardKey::F => ('а', 'А'),
+        KeyboardKey::G => ('п', 'П'),
+        KeyboardKey::H => ('р', 'Р'),
+        KeyboardKey::J => ('о', 'О'),
+        KeyboardKey::K => ('л', 'Л'),
+        KeyboardKey::L => ('д', 'Д'),
+        KeyboardKey::SemiColon => ('ж', 'Ж'),
+        KeyboardKey::Quote => ('э', 'Э'),
+        KeyboardKey::Z => ('я', 'Я'),
+        KeyboardKey::X => ('ч', 'Ч'),
+        KeyboardKey::C => ('с', 'С'),
+        KeyboardKey::V => ('м', 'М'),
+        KeyboardKey::B => ('и', 'И'),
+        KeyboardKey::N => ('т', 'Т'),
+        KeyboardKey::M => ('ь', 'Ь'),
+        KeyboardKey::Comma => ('б', 'Б'),
+        KeyboardKey::Period => ('ю', 'Ю'),
+        KeyboardKey::Tilde => ('ё', 'Ё'),
+        KeyboardKey::Num2 => ('2', '"'),
+        KeyboardKey::Num3 => ('3', '№'),
+        KeyboardKey::Num4 => ('4', ';'),
+        KeyboardKey::Num6 => ('6', ':'),
+        KeyboardKey::Num7 => ('7', '?'),
+        KeyboardKey::Slash => ('.', ','),
+        KeyboardKey::BackSlash => ('\\', '/'),
+        _ => return None,
+    };
+
+    Some(chars)
+}
+
+/// Key combination cycling through the layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayoutHotkey {
+    #[default]
+    AltShift,
+    ControlShift,
+    Key(KeyboardKey),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Left,
+    Right,
+    Home,
+    End,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEvent {
+    Char(char),
+    Backspace,
+    Delete,
+    /// Moves the cursor, `select` extends the selection instead of dropping it.
+    Move {
+        motion: Motion,
+        select: bool,
+    },
+    SelectAll,
+    Submit,
+    Cancel,
+    LayoutChanged(KeyboardLayout),
+}
+
+/// Turns keyboard events into typed characters and editing commands. Shift is tracked from the
+/// events themselves, so Shift released in the middle of a frame applies to the keys typed after
+/// it only. The other modifiers and Caps Lock come from `InputState::modifiers`. Held keys repeat
+/// the same way the keyboard repeats their `Down` events.
+#[derive(Debug, Clone, Default)]
+pub struct TextInput {
+    layout: KeyboardLayout,
+    hotkey: LayoutHotkey,
+    /// Left and right Shift.
+    shift: [bool; 2],
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_layout(mut self, layout: KeyboardLayout) -> Self {
+        self.layout = layout;
+
+        self
+    }
+
+    pub fn with_hotkey(mut self, hotkey: LayoutHotkey) -> Self {
+        self.hotkey = hotkey;
+
+        self
+    }
+
+    pub fn layout(&self) -> KeyboardLayout {
+        self.layout
+    }
+
+    pub fn set_layout(&mut self, layout: KeyboardLayout) {
+        self.layout = layout;
+    }
+
+    fn shift(&self) -> bool {
+        self.shift[0] || self.shift[1]
+    }
+
+    fn is_hotkey(&self, key: KeyboardKey, modifiers: Modifiers) -> bool {
+        let shift = matches!(key, KeyboardKey::LShift | KeyboardKey::RShift);
+        let alt = matches!(key, KeyboardKey::LAlt | KeyboardKey::RAlt);
+        let control = matches!(key, KeyboardKey::LControl | KeyboardKey::RControl);
+
+        match self.hotkey {
+            LayoutHotkey::AltShift => (shift && modifiers.alt) || (alt && self.shift()),
+            LayoutHotkey::ControlShift => (shift && modifiers.control) || (control && self.shift()),
+            LayoutHotkey::Key(hotkey) => key == hotkey,
+        }
+    }
+
+    /// Translates one event of the current frame of `input`, most events type nothing.
+    pub fn handle_event(&mut self, ev: &KeyboardEvent, input: &InputState) -> Option<TextEvent> {
+        let down = ev.state == KeyState::Down;
+        let repeat = match ev.key {
+            KeyboardKey::LShift => mem::replace(&mut self.shift[0], down),
+            KeyboardKey::RShift => mem::replace(&mut self.shift[1], down),
+            key => !input.just_pressed(key),
+        };
+
+        if !down {
+            return None;
+        }
+
+        let modifiers = input.modifiers();
+
+        if !repeat && self.is_hotkey(ev.key, modifiers) {
+            self.layout = self.layout.next();
+
+            return Some(TextEvent::LayoutChanged(self.layout));
+        }
+
+        let select = self.shift();
+        let motion = |motion| Some(TextEvent::Move { motion, select });
+
+        match ev.key {
+            KeyboardKey::BackSpace => Some(TextEvent::Backspace),
+            KeyboardKey::Delete => Some(TextEvent::Delete),
+            KeyboardKey::Left => motion(Motion::Left),
+            KeyboardKey::Right => motion(Motion::Right),
+            KeyboardKey::Home => motion(Motion::Home),
+            KeyboardKey::End => motion(Motion::End),
+            KeyboardKey::Return => Some(TextEvent::Submit),
+            KeyboardKey::Escape => Some(TextEvent::Cancel),
+            KeyboardKey::A if modifiers.control => Some(TextEvent::SelectAll),
+            // Shortcuts don't type.
+            _ if modifiers.control || modifiers.alt => None,
+            key => self
+                .layout
+                .translate(key, self.shift(), modifiers.caps_lock)
+                .map(TextEvent::Char),
+        }
+    }
+
+    /// Text events of the current frame, call once per frame after `InputState::update`.
+    pub fn update(&mut self, input: &InputState) -> Vec<TextEvent> {
+        input
+            .events()
+            .iter()
+            .filter_map(|ev| self.handle_event(ev, input))
+            .collect()
+    }
+}
@@ -0,0 +1,200 @@
+use alloc::{vec, vec::Vec};
+use gpu::{Color, Point, Rect};
+use render::RenderBackend;
+
+use crate::{gpu_object::GpuHandle, Sprite};
+
+/// Frames in flight, every buffer keeps its own copy of the drawn tiles.
+const BUFFERS: usize = 2;
+
+/// Grid of tiles from a tileset of atlas sprites, shown through a viewport of
+/// `visible_columns`x`visible_rows` cells that scrolls by whole tiles.
+///
+/// Tiles are drawn directly, not through a `RenderQueue`. A cell is redrawn only when its tile
+/// differs from the one drawn into the same buffer two flips ago, so nothing else may draw over
+/// the viewport between frames, or the map must be invalidated.
+#[derive(Debug)]
+pub struct Tilemap {
+    columns: usize,
+    rows: usize,
+    tile_width: f64,
+    tile_height: f64,
+    visible_columns: usize,
+    visible_rows: usize,
+    position: Point,
+    background: Color,
+    scroll: (usize, usize),
+    tileset: Vec<Sprite>,
+    cells: Vec<Option<usize>>,
+    /// Tiles of the viewport as drawn into each buffer, `None` when unknown.
+    drawn: [Vec<Option<Option<usize>>>; BUFFERS],
+    buffer: usize,
+}
+
+impl Tilemap {
+    pub fn new(
+        columns: usize,
+        rows: usize,
+        tile_width: f64,
+        tile_height: f64,
+        visible_columns: usize,
+        visible_rows: usize,
+    ) -> Self {
+        let visible = visible_columns * visible_rows;
+
+        Self {
+            columns,
+            rows,
+            tile_width,
+            tile_height,
+            visible_columns,
+            visible_rows,
+            position: Point::zero(),
+            background: Color::black(),
+            scroll: (0, 0),
+            tileset: Vec::new(),
+            cells: vec![None; columns * rows],
+            drawn: [vec![None; visible], vec![None; visible]],
+            buffer: 0,
+        }
+    }
+
+    /// Tiles are indices into `tileset`, cells with a missing index are left empty.
+    pub fn with_tileset(mut self, tileset: Vec<Sprite>) -> Self {
+        self.set_tileset(tileset);
+
+        self
+    }
+
+    pub fn set_tileset(&mut self, tileset: Vec<Sprite>) {
+        self.tileset = tileset;
+        self.invalidate();
+    }
+
+    pub fn with_position(mut self, position: Point) -> Self {
+        self.set_position(position);
+
+        self
+    }
+
+    pub fn set_position(&mut self, position: Point) {
+        self.position = position;
+        self.invalidate();
+    }
+
+    /// Color of empty cells, also drawn under every tile.
+    pub fn with_background(mut self, color: Color) -> Self {
+        self.background = color;
+        self.invalidate();
+
+        self
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Viewport on the screen.
+    pub fn bounds(&self) -> Rect {
+        Rect::new_from_position(
+            self.position,
+            self.visible_columns as f64 * self.tile_width,
+            self.visible_rows as f64 * self.tile_height,
+        )
+    }
+
+    pub fn tile(&self, column: usize, row: usize) -> Option<usize> {
+        if column >= self.columns || row >= self.rows {
+            return None;
+        }
+
+        self.cells[row * self.columns + column]
+    }
+
+    pub fn set_tile(&mut self, column: usize, row: usize, tile: Option<usize>) {
+        if column < self.columns && row < self.rows {
+            self.cells[row * self.columns + column] = tile;
+        }
+    }
+
+    pub fn fill(&mut self, tile: Option<usize>) {
+        self.cells.fill(tile);
+    }
+
+    /// Column and row of the top-left visible cell.
+    pub fn scroll(&self) -> (usize, usize) {
+        self.scroll
+    }
+
+    pub fn set_scroll(&mut self, column: usize, row: usize) {
+        self.scroll = (
+            usize::min(column, self.columns.saturating_sub(self.visible_columns)),
+            usize::min(row, self.rows.saturating_sub(self.visible_rows)),
+        );
+    }
+
+    pub fn scroll_by(&mut self, columns: isize, rows: isize) {
+        let (column, row) = self.scroll;
+
+        self.set_scroll(
+            column.saturating_add_signed(columns),
+            row.saturating_add_signed(rows),
+        );
+    }
+
+    /// Forces every visible cell to be drawn in the next frames.
+    pub fn invalidate(&mut self) {
+        for drawn in &mut self.drawn {
+            drawn.fill(None);
+        }
+    }
+
+    /// Draws the cells that changed since this buffer was last drawn, call once per frame.
+    /// Returns how many cells were drawn.
+    pub fn draw(&mut self, video: &mut impl RenderBackend) -> usize {
+        let (scroll_column, scroll_row) = self.scroll;
+        let mut count = 0;
+
+        video.push_state();
+        video.set_painter_color(self.background);
+
+        for row in 0..self.visible_rows {
+            for column in 0..self.visible_columns {
+                let tile = self
+                    .tile(scroll_column + column, scroll_row + row)
+                    .filter(|tile| *tile < self.tileset.len());
+                let slot = &mut self.drawn[self.buffer][row * self.visible_columns + column];
+
+                if *slot == Some(tile) {
+                    continue;
+                }
+
+                *slot = Some(tile);
+                count += 1;
+
+                let position = Point::new(
+                    self.position.x + column as f64 * self.tile_width,
+                    self.position.y + row as f64 * self.tile_height,
+                );
+                let cell = Rect::new_from_position(position, self.tile_width, self.tile_height);
+
+                video.draw_rect(cell);
+
+                if let Some(tile) = tile {
+                    let tile = &self.tileset[tile];
+
+                    video.draw_image_region(tile.id(), tile.source(), cell);
+                }
+            }
+        }
+
+        video.pop_state();
+        self.buffer = (self.buffer + 1) % BUFFERS;
+
+        count
+    }
+}
@@ -1,80 +1,518 @@
-use gpu::{Color, Gpu, GpuOp, ObjectType, Point, Rect, TextAlign};
+use alloc::collections::BTreeMap;
+use core::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
+
+use gpu::{Color, Gpu, GpuError, GpuOp, ObjectType, Point, Rect, TextAlign};
 use pci::PciBus;
+use render::{PainterState, RenderBackend, PAINTER_STACK_SIZE};
 use screen::Screen;
 
-static mut VIDEO: Option<Video> = None;
+use crate::{canvas::Canvas, gpu_object::GpuObject, Time};
+
+pub const MAX_SCREENS: usize = 4;
+
+/// One slot per screen, filled at init before the heap exists.
+static mut VIDEOS: [Option<Video>; MAX_SCREENS] = [NO_VIDEO; MAX_SCREENS];
+
+const NO_VIDEO: Option<Video> = None;
+
+const MAX_RETRIES: u32 = 2;
+const SCREEN_TIMEOUT: Duration = Duration::from_secs(2);
+const RECONNECT_INTERVAL: Duration = Duration::from_millis(500);
+/// Object ids handed out carry the generation in the bits above these, so an id from before a
+/// reinit never reaches the GPU, where it could name a newer object.
+const OBJECT_ID_BITS: u32 = 48;
+const GENERATIONS: u64 = 1 << (u64::BITS - OBJECT_ID_BITS);
+
+#[derive(Debug)]
+pub enum VideoError {
+    /// The driver failed the op with `error`.
+    Gpu {
+        op: &'static str,
+        error: GpuError,
+    },
+    /// The object was created before the GPU was reinitialized.
+    StaleObject(u64),
+    DeviceNotFound(&'static str),
+    ScreenTimeout,
+}
+
+impl Display for VideoError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            VideoError::Gpu { op, error } => write!(f, "GPU operation {op} failed: {error:?}"),
+            VideoError::StaleObject(object_id) => {
+                write!(f, "GPU object {object_id} was lost on reinit")
+            }
+            VideoError::DeviceNotFound(device) => write!(f, "no {device} on the PCI bus"),
+            VideoError::ScreenTimeout => write!(f, "screen did not connect to the GPU"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorAction {
+    Retry,
+    Degrade,
+    Escalate,
+}
+
+/// Decides what to do with a failed GPU operation, `attempt` counts the retries already made.
+pub type ErrorPolicy = fn(error: &VideoError, attempt: u32) -> ErrorAction;
+
+pub fn default_error_policy(error: &VideoError, attempt: u32) -> ErrorAction {
+    match error {
+        VideoError::Gpu { .. } if attempt < MAX_RETRIES => ErrorAction::Retry,
+        VideoError::Gpu { .. } | VideoError::StaleObject(_) => ErrorAction::Degrade,
+        VideoError::DeviceNotFound(_) | VideoError::ScreenTimeout => ErrorAction::Escalate,
+    }
+}
+
+pub fn degrade_error_policy(_error: &VideoError, _attempt: u32) -> ErrorAction {
+    ErrorAction::Degrade
+}
+
+/// Screen of a console with more than one display, `PRIMARY` is the one `Video::mut_video`
+/// returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ScreenHandle(usize);
+
+impl ScreenHandle {
+    pub const PRIMARY: ScreenHandle = ScreenHandle(0);
+
+    pub fn index(&self) -> usize {
+        self.0
+    }
+
+    pub fn video(&self) -> Option<&'static mut Video> {
+        unsafe { VIDEOS[self.0].as_mut() }
+    }
+}
 
 pub struct Video {
+    handle: ScreenHandle,
     gpu: Gpu,
     screen: Screen,
     bounds: Rect,
+    error_policy: ErrorPolicy,
+    painter: PainterState,
+    painter_stack: [PainterState; PAINTER_STACK_SIZE],
+    painter_stack_len: usize,
+    /// Pushes past the full stack, they are ignored and so are their pops.
+    painter_stack_overflow: usize,
+    live_objects: usize,
+    ops_count: u64,
+    frame_ops: u64,
+    image_sizes: BTreeMap<u64, (u64, u64)>,
+    /// Bumped when the GPU is reinitialized and its objects are gone.
+    generation: u64,
+    canvas: Option<Canvas>,
+    letterbox_color: Color,
+    connected: bool,
+    next_reconnect: Duration,
 }
 
 impl Video {
-    pub fn init(mut self) {
-        unsafe {
-            self.gpu
-                .call_op(GpuOp::Init {
-                    width: self.bounds.width() as u64,
-                    height: self.bounds.height() as u64,
-                })
-                .unwrap();
+    pub fn init(self) {
+        if let Err(error) = self.try_init() {
+            panic!("Video init failed: {error}");
+        }
+    }
+
+    /// Binds every screen on the bus to a GPU, in bus order, and initializes them. Screens that
+    /// fail to connect are skipped, the first one that works becomes `ScreenHandle::PRIMARY`.
+    pub fn init_all() -> usize {
+        let pci = PciBus::default();
+        let screens = pci.find_all_by_id(screen::DEVICE_ID).map(Screen::from);
+        let gpus = pci.find_all_by_id(gpu::DEVICE_ID).map(Gpu::from);
+        let mut count = 0;
+
+        for (screen, gpu) in screens.zip(gpus) {
+            if count == MAX_SCREENS {
+                break;
+            }
+
+            if Self::new(screen, gpu)
+                .try_init_as(ScreenHandle(count))
+                .is_ok()
+            {
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            panic!(
+                "Video init failed: {}",
+                VideoError::DeviceNotFound("screen")
+            );
+        }
+
+        count
+    }
+
+    /// Initializes the GPU and connects the screen to it, waiting at most `SCREEN_TIMEOUT`.
+    pub fn try_init(self) -> Result<(), VideoError> {
+        self.try_init_as(ScreenHandle::PRIMARY)
+    }
+
+    /// Same as `try_init`, the video is then reachable through `handle`.
+    pub fn try_init_as(mut self, handle: ScreenHandle) -> Result<(), VideoError> {
+        if handle.0 >= MAX_SCREENS {
+            return Err(VideoError::DeviceNotFound("screen slot"));
+        }
+
+        self.handle = handle;
+
+        let width = self.bounds.width() as u64;
+        let height = self.bounds.height() as u64;
+
+        self.call_op(GpuOp::Init { width, height }, "Init")?;
 
+        unsafe {
             self.screen.connect(self.gpu.device.mmio.address);
+        }
 
-            while !self.screen.is_connected() {}
+        if !self.wait_connected(SCREEN_TIMEOUT) {
+            return Err(VideoError::ScreenTimeout);
+        }
 
-            VIDEO.replace(self);
+        self.connected = true;
+
+        unsafe {
+            VIDEOS[handle.0].replace(self);
+        }
+
+        Ok(())
+    }
+
+    fn wait_connected(&self, timeout: Duration) -> bool {
+        let deadline = Time::now() + timeout;
+
+        loop {
+            if unsafe { self.screen.is_connected() } {
+                return true;
+            }
+
+            if Time::now() >= deadline {
+                return false;
+            }
+        }
+    }
+
+    /// Whether the screen was connected at the last flip. Drawing goes on while it is not, the
+    /// frames are just not shown.
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Called on every flip. A disconnected screen is looked up on the bus again and connected
+    /// back to the GPU every `RECONNECT_INTERVAL`, it may come back as another device. Screens
+    /// connected to other GPUs are left alone.
+    fn poll_connection(&mut self) {
+        if unsafe { self.screen.is_connected() } {
+            if !self.connected {
+                self.connected = true;
+                self.resize_to_screen();
+            }
+
+            return;
+        }
+
+        self.connected = false;
+
+        let now = Time::now();
+
+        if now < self.next_reconnect {
+            return;
+        }
+
+        self.next_reconnect = now + RECONNECT_INTERVAL;
+
+        if let Some(screen) = find_free_screen(&PciBus::default()) {
+            self.screen = screen;
+
+            unsafe {
+                self.screen.connect(self.gpu.device.mmio.address);
+            }
+        }
+    }
+
+    /// Reinitializes the GPU when the reconnected screen has another size, the canvas is fitted
+    /// into the new size. Init drops every GPU object, so the generation changes.
+    fn resize_to_screen(&mut self) {
+        let (width, height) = unsafe { (self.screen.width() as u64, self.screen.height() as u64) };
+
+        if width as f64 == self.bounds.width() && height as f64 == self.bounds.height() {
+            return;
+        }
+
+        self.bounds = Rect::new_from_zero(width as f64, height as f64);
+        self.handle((), |video| {
+            video
+                .call_op(GpuOp::Init { width, height }, "Init")
+                .map(|_| ())
+        });
+
+        // The GPU painter and objects start over after init, the owners of the objects create
+        // them again once they see the new generation.
+        self.painter = PainterState::default();
+        self.generation = (self.generation + 1) % GENERATIONS;
+        self.live_objects = 0;
+        self.image_sizes.clear();
+
+        if let Some(canvas) = self.canvas {
+            let bounds = canvas.bounds();
+
+            self.set_canvas(Some(Canvas::fit(
+                bounds.width(),
+                bounds.height(),
+                self.bounds,
+            )));
         }
     }
 
     pub fn width(&self) -> f64 {
-        self.bounds.width()
+        self.bounds().width()
     }
 
     pub fn height(&self) -> f64 {
-        self.bounds.height()
+        self.bounds().height()
     }
 
+    /// Bounds of the drawing area, that is the canvas when one is set.
     pub fn bounds(&self) -> Rect {
+        self.canvas.map_or(self.bounds, |canvas| canvas.bounds())
+    }
+
+    pub fn screen_bounds(&self) -> Rect {
         self.bounds
     }
 
-    pub fn create_text_object(&mut self, text: &str) -> u64 {
-        unsafe {
-            self.gpu
-                .call_op(GpuOp::CreateObject {
-                    ty: ObjectType::Text,
-                    address: text.as_ptr() as usize,
-                    size: 1,
-                    length: text.len(),
-                })
-                .unwrap() as u64
+    pub fn canvas(&self) -> Option<Canvas> {
+        self.canvas
+    }
+
+    /// Makes every draw operation use the logical coordinates of `canvas`, the rest of the
+    /// screen is covered with the letterbox color on each flip.
+    pub fn set_canvas(&mut self, canvas: Option<Canvas>) {
+        let text_size = self.painter.text_size;
+
+        self.canvas = canvas;
+
+        // The GPU keeps the scaled size, it has to be sent again in the new scale.
+        self.painter.text_size = None;
+
+        if let Some(size) = text_size {
+            self.set_painter_text_size(size);
         }
     }
 
-    pub fn delete_object(&mut self, object_id: u64) {
-        unsafe {
-            self.gpu.call_op(GpuOp::DeleteObject { object_id }).unwrap();
+    pub fn set_letterbox_color(&mut self, color: Color) {
+        self.letterbox_color = color;
+    }
+
+    fn scale(&self) -> f64 {
+        self.canvas.map_or(1.0, |canvas| canvas.scale())
+    }
+
+    fn to_screen(&self, point: Point) -> Point {
+        self.canvas.map_or(point, |canvas| canvas.to_screen(point))
+    }
+
+    fn to_screen_rect(&self, rect: Rect) -> Rect {
+        self.canvas
+            .map_or(rect, |canvas| canvas.to_screen_rect(rect))
+    }
+
+    pub fn error_policy(&self) -> ErrorPolicy {
+        self.error_policy
+    }
+
+    pub fn set_error_policy(&mut self, policy: ErrorPolicy) {
+        self.error_policy = policy;
+    }
+
+    pub fn live_objects(&self) -> usize {
+        self.live_objects
+    }
+
+    /// GPU ops issued during the last complete frame.
+    pub fn frame_ops(&self) -> u64 {
+        self.frame_ops
+    }
+
+    /// See `RenderBackend::generation`.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn tag_object_id(&self, gpu_object_id: u64) -> u64 {
+        (self.generation << OBJECT_ID_BITS) | gpu_object_id
+    }
+
+    fn gpu_object_id(&self, object_id: u64) -> Result<u64, VideoError> {
+        if object_id >> OBJECT_ID_BITS != self.generation {
+            return Err(VideoError::StaleObject(object_id));
         }
+
+        Ok(object_id & ((1 << OBJECT_ID_BITS) - 1))
+    }
+
+    pub fn painter_state(&self) -> PainterState {
+        self.painter
+    }
+
+    /// Saves the painter state. Too deep nesting doesn't panic, since the BSOD draws with this
+    /// too, the extra states are just not saved.
+    pub fn push_state(&mut self) {
+        if self.painter_stack_len >= PAINTER_STACK_SIZE {
+            self.painter_stack_overflow += 1;
+
+            return;
+        }
+
+        // Query unknown values once, so the state can be restored without extra Get ops later.
+        // The getters cache only values actually read, the rest stays unknown and isn't restored.
+        self.handle(None, |video| video.try_get_painter_color().map(Some));
+        self.handle(None, |video| video.try_get_painter_text_size().map(Some));
+        self.handle(None, |video| video.try_get_painter_text_align().map(Some));
+
+        self.painter_stack[self.painter_stack_len] = self.painter;
+        self.painter_stack_len += 1;
+    }
+
+    pub fn pop_state(&mut self) {
+        if self.painter_stack_overflow > 0 {
+            self.painter_stack_overflow -= 1;
+
+            return;
+        }
+
+        if self.painter_stack_len == 0 {
+            return;
+        }
+
+        self.painter_stack_len -= 1;
+        let state = self.painter_stack[self.painter_stack_len];
+
+        if let Some(color) = state.color {
+            self.set_painter_color(color);
+        }
+
+        if let Some(size) = state.text_size {
+            self.set_painter_text_size(size);
+        }
+
+        if let Some(align) = state.text_align {
+            self.set_painter_text_align(align);
+        }
+    }
+
+    /// Issues `op`, `name` tells which op failed in the error.
+    fn call_op(&mut self, op: GpuOp, name: &'static str) -> Result<f64, VideoError> {
+        self.ops_count += 1;
+
+        unsafe { self.gpu.call_op(op) }.map_err(|error| VideoError::Gpu { op: name, error })
+    }
+
+    /// Runs `op` under the current error policy, a degraded operation yields `fallback`.
+    fn handle<T>(
+        &mut self,
+        fallback: T,
+        mut op: impl FnMut(&mut Self) -> Result<T, VideoError>,
+    ) -> T {
+        let mut attempt = 0;
+
+        loop {
+            let error = match op(self) {
+                Ok(value) => return value,
+                Err(error) => error,
+            };
+
+            match (self.error_policy)(&error, attempt) {
+                ErrorAction::Retry => attempt += 1,
+                ErrorAction::Degrade => return fallback,
+                ErrorAction::Escalate => panic!("{error}"),
+            }
+        }
+    }
+
+    pub fn try_create_text_object(&mut self, text: &str) -> Result<u64, VideoError> {
+        let object_id = self.call_op(
+            GpuOp::CreateObject {
+                ty: ObjectType::Text,
+                address: text.as_ptr() as usize,
+                size: 1,
+                length: text.len(),
+            },
+            "CreateObject",
+        )?;
+        self.live_objects += 1;
+
+        Ok(self.tag_object_id(object_id as u64))
+    }
+
+    /// `None` when the error policy degraded the failure.
+    pub fn create_text_object(&mut self, text: &str) -> Option<u64> {
+        self.handle(None, |video| video.try_create_text_object(text).map(Some))
+    }
+
+    pub fn try_delete_object(&mut self, object_id: u64) -> Result<(), VideoError> {
+        let gpu_object_id = self.gpu_object_id(object_id)?;
+
+        self.call_op(
+            GpuOp::DeleteObject {
+                object_id: gpu_object_id,
+            },
+            "DeleteObject",
+        )?;
+        self.live_objects = self.live_objects.saturating_sub(1);
+        self.image_sizes.remove(&object_id);
+
+        Ok(())
+    }
+
+    pub fn delete_object(&mut self, object_id: u64) {
+        self.handle((), |video| video.try_delete_object(object_id))
     }
 
     pub fn set_font_size(&mut self, size: f64) {
-        unsafe {
-            self.gpu
-                .call_op(GpuOp::SetPainterTextSize { size })
-                .unwrap();
+        self.set_painter_text_size(size)
+    }
+
+    pub fn try_set_painter_color(&mut self, color: Color) -> Result<(), VideoError> {
+        if self.painter.color == Some(color) {
+            return Ok(());
         }
+
+        self.painter.color = None;
+        self.call_op(GpuOp::SetPainterColor { color }, "SetPainterColor")?;
+        self.painter.color = Some(color);
+
+        Ok(())
     }
 
     pub fn set_painter_color(&mut self, color: Color) {
-        unsafe {
-            self.gpu.call_op(GpuOp::SetPainterColor { color }).unwrap();
+        self.handle((), |video| video.try_set_painter_color(color))
+    }
+
+    pub fn try_get_painter_color(&mut self) -> Result<Color, VideoError> {
+        if let Some(color) = self.painter.color {
+            return Ok(color);
         }
+
+        let color = self
+            .call_op(GpuOp::GetPainterColor, "GetPainterColor")
+            .map(Color::from)?;
+        self.painter.color = Some(color);
+
+        Ok(color)
     }
 
     pub fn get_painter_color(&mut self) -> Color {
-        unsafe { self.gpu.call_op(GpuOp::GetPainterColor).unwrap().into() }
+        self.handle(Color::white(), |video| video.try_get_painter_color())
     }
 
     pub fn swap_painter_color(&mut self, new_color: Color) -> Color {
@@ -91,92 +529,172 @@ impl Video {
         old_size
     }
 
+    pub fn try_get_painter_text_size(&mut self) -> Result<f64, VideoError> {
+        if let Some(size) = self.painter.text_size {
+            return Ok(size);
+        }
+
+        let size = self.call_op(GpuOp::GetPainterTextSize, "GetPainterTextSize")? / self.scale();
+        self.painter.text_size = Some(size);
+
+        Ok(size)
+    }
+
     pub fn get_painter_text_size(&mut self) -> f64 {
-        unsafe { self.gpu.call_op(GpuOp::GetPainterTextSize).unwrap() }
+        self.handle(0.0, |video| video.try_get_painter_text_size())
     }
 
-    pub fn set_painter_text_size(&mut self, size: f64) {
-        unsafe {
-            self.gpu
-                .call_op(GpuOp::SetPainterTextSize { size })
-                .unwrap();
+    pub fn try_set_painter_text_size(&mut self, size: f64) -> Result<(), VideoError> {
+        if self.painter.text_size == Some(size) {
+            return Ok(());
         }
+
+        self.painter.text_size = None;
+        self.call_op(
+            GpuOp::SetPainterTextSize {
+                size: size * self.scale(),
+            },
+            "SetPainterTextSize",
+        )?;
+        self.painter.text_size = Some(size);
+
+        Ok(())
+    }
+
+    pub fn set_painter_text_size(&mut self, size: f64) {
+        self.handle((), |video| video.try_set_painter_text_size(size))
+    }
+
+    pub fn try_fill_screen(&mut self, color: Option<Color>) -> Result<(), VideoError> {
+        self.push_state();
+
+        let result = color
+            .map_or(Ok(()), |color| self.try_set_painter_color(color))
+            .and_then(|_| self.try_draw_rect(self.bounds()));
+
+        self.pop_state();
+
+        result
     }
 
     pub fn fill_screen(&mut self, color: Option<Color>) {
-        unsafe {
-            let mut old_color: Option<Color> = None;
+        self.handle((), |video| video.try_fill_screen(color))
+    }
 
-            if let Some(color) = color {
-                old_color = Some(self.swap_painter_color(color));
-            }
+    pub fn try_draw_rect(&mut self, rect: Rect) -> Result<(), VideoError> {
+        let rect = self.to_screen_rect(rect);
+
+        self.call_op(
+            GpuOp::DrawRect {
+                from: rect.position(),
+                width: rect.width(),
+                height: rect.height(),
+            },
+            "DrawRect",
+        )
+        .map(|_| ())
+    }
 
-            self.gpu
-                .call_op(GpuOp::DrawRect {
-                    from: Point::new(0.0, 0.0),
-                    width: self.bounds.width(),
-                    height: self.bounds.height(),
-                })
-                .unwrap();
+    pub fn draw_rect(&mut self, rect: Rect) {
+        self.handle((), |video| video.try_draw_rect(rect))
+    }
 
-            if let Some(old_color) = old_color {
-                self.set_painter_color(old_color)
-            }
-        }
+    pub fn try_draw_text(&mut self, object_id: u64, position: Point) -> Result<(), VideoError> {
+        let object_id = self.gpu_object_id(object_id)?;
+        let position = self.to_screen(position);
+
+        self.call_op(
+            GpuOp::DrawText {
+                object_id,
+                position,
+            },
+            "DrawText",
+        )
+        .map(|_| ())
     }
 
     pub fn draw_text(&mut self, object_id: u64, position: Point) {
-        unsafe {
-            self.gpu
-                .call_op(GpuOp::DrawText {
-                    object_id,
-                    position,
-                })
-                .unwrap();
-        }
+        self.handle((), |video| video.try_draw_text(object_id, position))
+    }
+
+    pub fn try_draw_string(&mut self, text: &str, position: Point) -> Result<(), VideoError> {
+        let position = self.to_screen(position);
+        let address = text as *const str as *const u8 as usize;
+
+        self.call_op(
+            GpuOp::DrawString {
+                position,
+                address,
+                length: text.as_bytes().len(),
+            },
+            "DrawString",
+        )
+        .map(|_| ())
     }
 
     pub fn draw_string(&mut self, text: &str, position: Point) {
-        unsafe {
-            let address = text as *const str as *const u8 as usize;
+        self.handle((), |video| video.try_draw_string(text, position))
+    }
 
-            self.gpu
-                .call_op(GpuOp::DrawString {
-                    position,
-                    address,
-                    length: text.as_bytes().len(),
-                })
-                .unwrap();
-        }
+    pub fn try_measure_string(&mut self, text: &str) -> Result<f64, VideoError> {
+        let address = text as *const str as *const u8 as usize;
+
+        self.call_op(
+            GpuOp::MesaureString {
+                address,
+                length: text.as_bytes().len(),
+            },
+            "MesaureString",
+        )
+        .map(|width| width / self.scale())
     }
 
     pub fn measure_string(&mut self, text: &str) -> f64 {
-        unsafe {
-            let address = text as *const str as *const u8 as usize;
+        self.handle(0.0, |video| video.try_measure_string(text))
+    }
 
-            self.gpu
-                .call_op(GpuOp::MesaureString {
-                    address,
-                    length: text.as_bytes().len(),
-                })
-                .unwrap()
-        }
+    pub fn try_mesaure_text(&mut self, object_id: u64) -> Result<f64, VideoError> {
+        let object_id = self.gpu_object_id(object_id)?;
+
+        self.call_op(GpuOp::MesaureText { object_id }, "MesaureText")
+            .map(|width| width / self.scale())
     }
 
     pub fn mesaure_text(&mut self, object_id: u64) -> f64 {
-        unsafe { self.gpu.call_op(GpuOp::MesaureText { object_id }).unwrap() }
+        self.handle(0.0, |video| video.try_mesaure_text(object_id))
+    }
+
+    pub fn try_set_painter_text_align(&mut self, align: TextAlign) -> Result<(), VideoError> {
+        if self.painter.text_align == Some(align) {
+            return Ok(());
+        }
+
+        self.painter.text_align = None;
+        self.call_op(GpuOp::SetPainterTextAlign { align }, "SetPainterTextAlign")?;
+        self.painter.text_align = Some(align);
+
+        Ok(())
     }
 
     pub fn set_painter_text_align(&mut self, align: TextAlign) {
-        unsafe {
-            self.gpu
-                .call_op(GpuOp::SetPainterTextAlign { align })
-                .unwrap();
+        self.handle((), |video| video.try_set_painter_text_align(align))
+    }
+
+    pub fn try_get_painter_text_align(&mut self) -> Result<TextAlign, VideoError> {
+        if let Some(align) = self.painter.text_align {
+            return Ok(align);
         }
+
+        let align: TextAlign = self
+            .call_op(GpuOp::GetPainterTextAlign, "GetPainterTextAlign")
+            .map(|align| (align as u8).into())?;
+        self.painter.text_align = Some(align);
+
+        Ok(align)
     }
 
     pub fn get_painter_text_align(&mut self) -> TextAlign {
-        unsafe { (self.gpu.call_op(GpuOp::GetPainterTextAlign).unwrap() as u8).into() }
+        self.handle(TextAlign::Left, |video| video.try_get_painter_text_align())
     }
 
     pub fn swap_painter_text_align(&mut self, align: TextAlign) -> TextAlign {
@@ -186,66 +704,347 @@ impl Video {
         old_align
     }
 
-    pub fn create_image(&mut self, data: &[u8], width: u64, height: u64) -> u64 {
-        unsafe {
-            self.gpu
-                .call_op(GpuOp::CreateImageObject {
-                    width,
-                    height,
-                    address: data.as_ptr() as usize,
-                })
-                .unwrap() as u64
-        }
+    pub fn try_create_image(
+        &mut self,
+        data: &[u8],
+        width: u64,
+        height: u64,
+    ) -> Result<u64, VideoError> {
+        let object_id = self.call_op(
+            GpuOp::CreateImageObject {
+                width,
+                height,
+                address: data.as_ptr() as usize,
+            },
+            "CreateImageObject",
+        )? as u64;
+        let object_id = self.tag_object_id(object_id);
+
+        self.live_objects += 1;
+        self.image_sizes.insert(object_id, (width, height));
+
+        Ok(object_id)
     }
 
-    pub fn draw_image(&mut self, object_id: u64, position: Point) {
-        unsafe {
-            self.gpu
-                .call_op(GpuOp::DrawImage {
+    /// `None` when the error policy degraded the failure.
+    pub fn create_image(&mut self, data: &[u8], width: u64, height: u64) -> Option<u64> {
+        self.handle(None, |video| {
+            video.try_create_image(data, width, height).map(Some)
+        })
+    }
+
+    pub fn try_draw_image(&mut self, object_id: u64, position: Point) -> Result<(), VideoError> {
+        let scale = self.scale();
+
+        if scale != 1.0 {
+            if let Some(&(width, height)) = self.image_sizes.get(&object_id) {
+                return self.try_draw_image_rect(
                     object_id,
-                    position,
-                })
-                .unwrap();
+                    Rect::new_from_position(position, width as f64, height as f64),
+                );
+            }
         }
+
+        let object_id = self.gpu_object_id(object_id)?;
+        let position = self.to_screen(position);
+
+        self.call_op(
+            GpuOp::DrawImage {
+                object_id,
+                position,
+            },
+            "DrawImage",
+        )
+        .map(|_| ())
+    }
+
+    pub fn draw_image(&mut self, object_id: u64, position: Point) {
+        self.handle((), |video| video.try_draw_image(object_id, position))
+    }
+
+    pub fn try_draw_image_rect(&mut self, object_id: u64, dst: Rect) -> Result<(), VideoError> {
+        let object_id = self.gpu_object_id(object_id)?;
+        let dst = self.to_screen_rect(dst);
+
+        self.call_op(GpuOp::DrawImageRect { object_id, dst }, "DrawImageRect")
+            .map(|_| ())
     }
 
     pub fn draw_image_rect(&mut self, object_id: u64, dst: Rect) {
-        unsafe {
-            self.gpu
-                .call_op(GpuOp::DrawImageRect { object_id, dst })
-                .unwrap();
+        self.handle((), |video| video.try_draw_image_rect(object_id, dst))
+    }
+
+    /// `src` is in image pixels, only `dst` is mapped to the screen.
+    pub fn try_draw_image_region(
+        &mut self,
+        object_id: u64,
+        src: Rect,
+        dst: Rect,
+    ) -> Result<(), VideoError> {
+        let object_id = self.gpu_object_id(object_id)?;
+        let dst = self.to_screen_rect(dst);
+
+        self.call_op(
+            GpuOp::DrawImageRegion {
+                object_id,
+                src,
+                dst,
+            },
+            "DrawImageRegion",
+        )
+        .map(|_| ())
+    }
+
+    pub fn draw_image_region(&mut self, object_id: u64, src: Rect, dst: Rect) {
+        self.handle((), |video| video.try_draw_image_region(object_id, src, dst))
+    }
+
+    fn draw_letterbox(&mut self) {
+        let Some(canvas) = self.canvas else {
+            return;
+        };
+
+        let screen = self.bounds;
+        let area = canvas.screen_rect();
+        let (left, top) = (area.position().x, area.position().y);
+        let (right, bottom) = (left + area.width(), top + area.height());
+        let bars = [
+            Rect::new(0.0, 0.0, screen.width(), top),
+            Rect::new(0.0, bottom, screen.width(), screen.height()),
+            Rect::new(0.0, top, left, bottom),
+            Rect::new(right, top, screen.width(), bottom),
+        ];
+
+        self.push_state();
+        self.set_painter_color(self.letterbox_color);
+
+        for bar in bars {
+            if bar.width() <= 0.0 || bar.height() <= 0.0 {
+                continue;
+            }
+
+            self.handle((), |video| {
+                video
+                    .call_op(
+                        GpuOp::DrawRect {
+                            from: bar.position(),
+                            width: bar.width(),
+                            height: bar.height(),
+                        },
+                        "DrawRect",
+                    )
+                    .map(|_| ())
+            });
         }
+
+        self.pop_state();
     }
 
     pub fn flip_buffers(&mut self) {
+        self.draw_letterbox();
+
         unsafe {
             self.gpu.flip_buffers();
         }
+
+        // The flip doesn't go through `call_op` but is an op of the frame too.
+        self.ops_count += 1;
+        self.poll_connection();
+
+        // Secondary screens may not flip every frame, so any flip releases the objects of all.
+        GpuObject::release_pending(self);
+
+        for handle in Self::screens().filter(|handle| *handle != self.handle) {
+            if let Some(video) = handle.video() {
+                GpuObject::release_pending(video);
+            }
+        }
+
+        GpuObject::forget_pending(|device| Self::screens().any(|handle| handle.0 == device));
+        self.frame_ops = self.ops_count;
+        self.ops_count = 0;
+    }
+
+    pub fn screen_handle(&self) -> ScreenHandle {
+        self.handle
+    }
+
+    /// Handles of the initialized screens.
+    pub fn screens() -> impl Iterator<Item = ScreenHandle> {
+        (0..MAX_SCREENS)
+            .map(ScreenHandle)
+            .filter(|handle| unsafe { VIDEOS[handle.0].is_some() })
     }
 
     pub fn mut_video() -> &'static mut Option<Video> {
-        unsafe { &mut VIDEO }
+        unsafe { &mut VIDEOS[ScreenHandle::PRIMARY.0] }
     }
 
     pub fn video() -> &'static Option<Video> {
-        unsafe { &VIDEO }
+        unsafe { &VIDEOS[ScreenHandle::PRIMARY.0] }
     }
 }
 
-impl Default for Video {
-    fn default() -> Self {
+/// Canvas scaling and the error policy apply the same way as for the inherent methods.
+impl RenderBackend for Video {
+    fn device_index(&self) -> usize {
+        self.handle.0
+    }
+
+    fn generation(&self) -> u64 {
+        Video::generation(self)
+    }
+
+    fn bounds(&self) -> Rect {
+        Video::bounds(self)
+    }
+
+    fn create_text_object(&mut self, text: &str) -> Option<u64> {
+        Video::create_text_object(self, text)
+    }
+
+    fn create_image(&mut self, data: &[u8], width: u64, height: u64) -> Option<u64> {
+        Video::create_image(self, data, width, height)
+    }
+
+    fn delete_object(&mut self, object_id: u64) {
+        Video::delete_object(self, object_id)
+    }
+
+    fn push_state(&mut self) {
+        Video::push_state(self)
+    }
+
+    fn pop_state(&mut self) {
+        Video::pop_state(self)
+    }
+
+    fn set_painter_color(&mut self, color: Color) {
+        Video::set_painter_color(self, color)
+    }
+
+    fn get_painter_color(&mut self) -> Color {
+        Video::get_painter_color(self)
+    }
+
+    fn set_painter_text_size(&mut self, size: f64) {
+        Video::set_painter_text_size(self, size)
+    }
+
+    fn get_painter_text_size(&mut self) -> f64 {
+        Video::get_painter_text_size(self)
+    }
+
+    fn set_painter_text_align(&mut self, align: TextAlign) {
+        Video::set_painter_text_align(self, align)
+    }
+
+    fn get_painter_text_align(&mut self) -> TextAlign {
+        Video::get_painter_text_align(self)
+    }
+
+    fn draw_rect(&mut self, rect: Rect) {
+        Video::draw_rect(self, rect)
+    }
+
+    fn fill_screen(&mut self, color: Option<Color>) {
+        Video::fill_screen(self, color)
+    }
+
+    fn draw_text(&mut self, object_id: u64, position: Point) {
+        Video::draw_text(self, object_id, position)
+    }
+
+    fn draw_string(&mut self, text: &str, position: Point) {
+        Video::draw_string(self, text, position)
+    }
+
+    fn measure_string(&mut self, text: &str) -> f64 {
+        Video::measure_string(self, text)
+    }
+
+    fn mesaure_text(&mut self, object_id: u64) -> f64 {
+        Video::mesaure_text(self, object_id)
+    }
+
+    fn draw_image(&mut self, object_id: u64, position: Point) {
+        Video::draw_image(self, object_id, position)
+    }
+
+    fn draw_image_rect(&mut self, object_id: u64, dst: Rect) {
+        Video::draw_image_rect(self, object_id, dst)
+    }
+
+    fn draw_image_region(&mut self, object_id: u64, src: Rect, dst: Rect) {
+        Video::draw_image_region(self, object_id, src, dst)
+    }
+
+    fn flip_buffers(&mut self) {
+        Video::flip_buffers(self)
+    }
+}
+
+fn find_screen(pci: &PciBus) -> Result<Screen, VideoError> {
+    pci.find_by_id(screen::DEVICE_ID)
+        .map(Screen::from)
+        .ok_or(VideoError::DeviceNotFound("screen"))
+}
+
+/// First screen on the bus not connected to any GPU.
+fn find_free_screen(pci: &PciBus) -> Option<Screen> {
+    pci.find_all_by_id(screen::DEVICE_ID)
+        .map(Screen::from)
+        .find(|screen| unsafe { !screen.is_connected() })
+}
+
+impl Video {
+    /// Finds the screen and the GPU, `init` must be called before drawing.
+    pub fn try_default() -> Result<Self, VideoError> {
+        let pci = PciBus::default();
+        let screen = find_screen(&pci)?;
+        let gpu = pci
+            .find_by_id(gpu::DEVICE_ID)
+            .map(Gpu::from)
+            .ok_or(VideoError::DeviceNotFound("GPU"))?;
+
+        Ok(Self::new(screen, gpu))
+    }
+
+    /// Binds `screen` to `gpu`, `try_init_as` must be called before drawing.
+    pub fn new(screen: Screen, gpu: Gpu) -> Self {
         unsafe {
-            let pci = PciBus::default();
-            let screen = pci.find_by_id(screen::DEVICE_ID).map(Screen::from).unwrap();
-            let gpu = pci.find_by_id(gpu::DEVICE_ID).map(Gpu::from).unwrap();
             let height = screen.height() as f64;
             let width = screen.width() as f64;
 
             Self {
+                handle: ScreenHandle::PRIMARY,
                 gpu,
                 screen,
                 bounds: Rect::new_from_zero(width, height),
+                error_policy: default_error_policy,
+                painter: PainterState::default(),
+                painter_stack: [PainterState::default(); PAINTER_STACK_SIZE],
+                painter_stack_len: 0,
+                painter_stack_overflow: 0,
+                live_objects: 0,
+                ops_count: 0,
+                frame_ops: 0,
+                image_sizes: BTreeMap::new(),
+                generation: 0,
+                canvas: None,
+                letterbox_color: Color::black(),
+                connected: false,
+                next_reconnect: Duration::ZERO,
             }
         }
     }
 }
+
+impl Default for Video {
+    fn default() -> Self {
+        match Self::try_default() {
+            Ok(video) => video,
+            Err(error) => panic!("{error}"),
+        }
+    }
+}